@@ -1,4 +1,12 @@
 pub mod account;
 pub mod auth;
 pub mod contracts;
+pub mod custody;
+pub mod identity_index;
+pub mod multisig;
+pub mod paymaster;
+pub mod recovery;
 pub mod sign;
+pub mod signers;
+pub mod sub_account;
+pub mod timelock;
@@ -1,56 +1,166 @@
-use near_sdk::env;
+use interfaces::traits::signable_message::SignableMessage;
+use near_sdk::{env, json_types::U128, require, Promise, PromiseError};
 use near_sdk_contract_tools::nft::nep145::{Nep145, Nep145Controller};
 
+use crate::contract::recovery::validate_recovery_config;
+use crate::contract::timelock::validate_timelock_policy;
+use crate::types::transaction::{AccountCreation, CreationAuth};
+use crate::types::view::{IdentityFilter, Page, DEFAULT_PAGE_LIMIT};
 use crate::*;
 
 #[near]
 impl AbstractAccountContract {
+    /// Creates an account controlled by the given identity
+    ///
+    /// # Arguments
+    /// * `account_id` - The id of the new account
+    /// * `identity_with_permissions` - The first identity of the account
+    /// * `auth` - Credentials of the identity over the AccountCreation message. When omitted the account is created
+    ///   without verifying the identity, unless the contract requires a creation proof
+    ///
+    /// # Returns
+    /// The verification promise when a creation proof is given, the account is stored in add_account_callback
     pub fn add_account(
         &mut self,
-        account_id: String,
+        account_id: AbstractAccountId,
         identity_with_permissions: IdentityWithPermissions,
-    ) {
-        let storage_usage_start = env::storage_usage();
+        auth: Option<CreationAuth>,
+    ) -> Option<Promise> {
         let predecessor = env::predecessor_account_id();
-        self.storage_balance_of(predecessor.clone())
-            .unwrap_or_else(|| env::panic_str("Predecessor has not registered for storage"));
+        self.validate_new_account(&predecessor, &account_id);
 
-        if self.accounts.contains_key(&account_id) {
-            env::panic_str("Account already exists");
+        let Some(auth) = auth else {
+            require!(
+                !self.creation_proof_required,
+                "Proof of the identity ownership required"
+            );
+
+            // Without a proof, if the account creator doesn't own the identity,
+            // subsequent method calls will fail automatically
+            self.create_account(&predecessor, account_id, identity_with_permissions);
+            return None;
+        };
+
+        let identity = identity_with_permissions.identity.clone();
+        let encoding = auth
+            .encoding
+            .unwrap_or_else(|| identity.default_message_encoding());
+        let signed_message = AccountCreation {
+            account_id: &account_id,
+            identity_with_permissions: &identity_with_permissions,
         }
+        .encode_message(&self.domain(encoding), encoding);
 
-        // No permission check needed - if account creator doesn't own the identity,
-        // subsequent method calls will fail automatically
-        self.accounts.insert(
-            account_id.to_string(),
-            Account::new(vec![identity_with_permissions], self.max_nonce),
-        );
-        self.accounts.flush();
+        Some(self.validate_credentials(
+            identity,
+            auth.credentials,
+            signed_message,
+            Self::ext(env::current_account_id()).add_account_callback(
+                predecessor,
+                account_id,
+                identity_with_permissions,
+            ),
+        ))
+    }
 
-        self.storage_accounting(&predecessor, storage_usage_start)
-            .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+    #[private]
+    pub fn add_account_callback(
+        &mut self,
+        predecessor: AccountId,
+        account_id: AbstractAccountId,
+        identity_with_permissions: IdentityWithPermissions,
+        #[callback_result] auth_result: Result<bool, PromiseError>,
+    ) {
+        match auth_result {
+            Ok(true) => {
+                // The account id may have been taken while the credentials were being verified
+                self.validate_new_account(&predecessor, &account_id);
+                self.create_account(&predecessor, account_id, identity_with_permissions);
+            }
+            Ok(false) => env::panic_str("Authentication failed"),
+            _ => env::panic_str("Failed to verify the identity ownership"),
+        }
+    }
+
+    #[private]
+    pub fn set_creation_proof_required(&mut self, required: bool) {
+        self.creation_proof_required = required;
+    }
+
+    pub fn is_creation_proof_required(&self) -> bool {
+        self.creation_proof_required
     }
 
+    /// Reserves account ids so they can't be created, e.g. brand names or ids kept for the deployer
     #[private]
-    pub fn remove_account(&mut self, account_id: String) {
-        let account_nonce = self.accounts.get(&account_id).unwrap().nonce;
-        if account_nonce > self.max_nonce {
-            self.max_nonce = account_nonce;
+    pub fn reserve_account_ids(&mut self, account_ids: Vec<AbstractAccountId>) {
+        for account_id in account_ids {
+            self.reserved_account_ids.insert(account_id);
         }
+    }
+
+    #[private]
+    pub fn release_account_ids(&mut self, account_ids: Vec<AbstractAccountId>) {
+        for account_id in account_ids {
+            self.reserved_account_ids.remove(&account_id);
+        }
+    }
 
-        self.accounts.remove(&account_id);
+    pub fn is_account_id_reserved(&self, account_id: AbstractAccountId) -> bool {
+        self.reserved_account_ids.contains(&account_id)
+    }
+
+    /// Adds the attached deposit to the account balance. Anyone can fund an account
+    #[payable]
+    pub fn deposit(&mut self, account_id: AbstractAccountId) -> U128 {
+        let account = self
+            .accounts
+            .get_mut(&account_id)
+            .expect("Account not found");
+
+        account.balance = U128(account.balance.0 + env::attached_deposit().as_yoctonear());
+
+        account.balance
+    }
+
+    #[private]
+    pub fn remove_account(&mut self, account_id: AbstractAccountId) {
+        // Withdraw and token transfers can precede RemoveAccount in the same transaction
+        let account = self.accounts.get(&account_id).unwrap();
+        require!(
+            account.balance.0 == 0,
+            "Withdraw the account balance before removing the account"
+        );
+        require!(
+            !account.holds_tokens(),
+            "Transfer the account tokens before removing the account"
+        );
+        // A recreated account_id would otherwise control the sub-account
+        require!(
+            account.sub_account.is_none(),
+            "Delete the sub-account before removing the account"
+        );
+
+        let account = self.accounts.remove(&account_id).unwrap();
+        for identity_with_permissions in account.identities {
+            self.unindex_identity(&identity_with_permissions.identity, &account_id);
+        }
+
+        // Proposals of a removed account can't be executed anymore
+        self.remove_account_proposals(&account_id, false);
     }
 
     #[private]
     #[payable]
     pub fn add_identity(
         &mut self,
-        account_id: String,
+        account_id: AbstractAccountId,
         identity_with_permissions: IdentityWithPermissions,
         #[callback_result] auth_result: Result<bool, near_sdk::PromiseError>,
     ) {
         match auth_result {
             Ok(true) => {
+                self.index_identity(&identity_with_permissions.identity, &account_id);
                 self.accounts
                     .get_mut(&account_id)
                     .unwrap()
@@ -62,68 +172,132 @@ impl AbstractAccountContract {
     }
 
     #[private]
-    pub fn remove_identity(&mut self, account_id: String, identity: &Identity) {
-        let account = self.accounts.get_mut(&account_id).unwrap();
-        account.remove_identity(identity);
+    pub fn remove_identity(&mut self, account_id: AbstractAccountId, identity: &Identity) {
+        self.accounts
+            .get_mut(&account_id)
+            .unwrap()
+            .remove_identity(identity);
+        self.unindex_identity(identity, &account_id);
 
+        let account = self.accounts.get(&account_id).unwrap();
         if account.identities.is_empty() {
-            self.accounts.remove(&account_id);
+            self.remove_account(account_id);
+        } else if let Some(ref policy) = account.multisig_policy {
+            require!(
+                account.full_access_identities_count() >= policy.threshold,
+                "Removing the identity would make the multisig threshold unreachable"
+            );
         }
     }
 
-    pub fn get_account_by_id(&self, account_id: String) -> Option<&Account> {
+    pub fn get_account_by_id(&self, account_id: AbstractAccountId) -> Option<&Account> {
         self.accounts.get(&account_id)
     }
 
-    pub fn list_account_ids(&self) -> Vec<String> {
-        self.accounts.iter().map(|(key, _)| key.clone()).collect()
-    }
-
-    pub fn list_identities(&self, account_id: String) -> Option<Vec<IdentityWithPermissions>> {
+    /// Next nonce of the nonce_key lane, None if the account doesn't exist
+    pub fn get_nonce(&self, account_id: AbstractAccountId, nonce_key: u64) -> Option<U128> {
         self.accounts
             .get(&account_id)
-            .map(|account| account.identities.clone())
+            .map(|account| U128(account.nonce(nonce_key)))
     }
 
-    pub fn get_account_by_identity(&self, identity: Identity) -> Vec<String> {
+    pub fn list_account_ids(
+        &self,
+        from_index: Option<u32>,
+        limit: Option<u32>,
+    ) -> Vec<AbstractAccountId> {
         self.accounts
-            .iter()
-            .filter(|(_, account)| account.get_identity(&identity).is_some())
-            .map(|(key, _)| key.clone())
+            .keys()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(DEFAULT_PAGE_LIMIT) as usize)
+            .cloned()
             .collect()
     }
 
+    pub fn get_accounts_count(&self) -> u32 {
+        self.accounts.len()
+    }
+
+    /// Iterates the accounts with at least one identity matching the filter.
+    /// Removing an account moves the last one to its position, so an iteration running meanwhile can miss it
+    ///
+    /// # Arguments
+    /// * `cursor` - The next_cursor of the previous page, the first account when omitted
+    /// * `limit` - Maximum number of accounts read, fewer are returned when some don't match the filter
+    /// * `filter` - Selects the accounts by their identities, every account when omitted
+    pub fn list_accounts(
+        &self,
+        cursor: Option<u32>,
+        limit: Option<u32>,
+        filter: Option<IdentityFilter>,
+    ) -> Page<(AbstractAccountId, Account)> {
+        let filter = filter.unwrap_or_default();
+
+        self.page_accounts(cursor, limit, |account_id, account| {
+            account
+                .identities
+                .iter()
+                .any(|identity| filter.matches(identity))
+                .then(|| (account_id.clone(), account.clone()))
+        })
+    }
+
+    /// Iterates the identities of every account, see list_accounts for the cursor and limit
+    pub fn list_all_identities(
+        &self,
+        cursor: Option<u32>,
+        limit: Option<u32>,
+        filter: Option<IdentityFilter>,
+    ) -> Page<(AbstractAccountId, IdentityWithPermissions)> {
+        let filter = filter.unwrap_or_default();
+
+        self.page_accounts(cursor, limit, |account_id, account| {
+            account
+                .identities
+                .iter()
+                .filter(|identity| filter.matches(identity))
+                .map(|identity| (account_id.clone(), identity.clone()))
+                .collect::<Vec<_>>()
+        })
+    }
+
+    pub fn list_identities(
+        &self,
+        account_id: AbstractAccountId,
+        filter: Option<IdentityFilter>,
+    ) -> Option<Vec<IdentityWithPermissions>> {
+        let filter = filter.unwrap_or_default();
+
+        self.accounts.get(&account_id).map(|account| {
+            account
+                .identities
+                .iter()
+                .filter(|identity| filter.matches(identity))
+                .cloned()
+                .collect()
+        })
+    }
+
     #[private]
     pub fn handle_account_action(
         &mut self,
         predecessor: AccountId,
-        account_id: String,
+        account_id: AbstractAccountId,
         action: Action,
     ) {
         let storage_usage_start = env::storage_usage();
         self.storage_balance_of(predecessor.clone())
             .expect("Predecessor has not registered for storage");
 
-        let account = self.accounts.get(&account_id).unwrap();
+        require!(self.accounts.contains_key(&account_id), "Account not found");
 
-        match action.clone() {
+        match action {
             Action::RemoveAccount => {
                 self.remove_account(account_id);
             }
             Action::AddIdentityWithAuth(auth) => {
-                // Decrement nonce since it was incremented on auth method
-                let signed_message = action.to_signed_message((&account_id, account.nonce - 1));
-
-                let mut identity = auth.identity_with_permissions.identity.clone();
-                identity.inject_webauthn_compressed_public_key(&account);
-
-                self.validate_credentials(
-                    identity,
-                    auth.credentials,
-                    signed_message,
-                    Self::ext(env::current_account_id())
-                        .add_identity(account_id, auth.identity_with_permissions),
-                );
+                // Credentials are verified before the transaction actions are executed
+                self.add_identity(account_id, auth.identity_with_permissions, Ok(true));
             }
             Action::AddIdentity(identity_with_permissions) => {
                 // If the auth identity doesn't have enable_act_as permission, we don't need its authorization
@@ -141,6 +315,102 @@ impl AbstractAccountContract {
             Action::RemoveIdentity(identity) => {
                 self.remove_identity(account_id, &identity);
             }
+            Action::SetMultisigPolicy(policy) => {
+                let account = self.accounts.get_mut(&account_id).unwrap();
+
+                if let Some(ref policy) = policy {
+                    require!(
+                        policy.threshold >= 1
+                            && policy.threshold <= account.full_access_identities_count(),
+                        "Multisig threshold must be between 1 and the number of full access identities"
+                    );
+                }
+
+                account.multisig_policy = policy;
+            }
+            Action::CancelProposal(proposal_id) => {
+                let proposal = self
+                    .proposals
+                    .get(&proposal_id)
+                    .expect("Proposal not found");
+
+                require!(
+                    proposal.transaction.account_id == account_id,
+                    "Proposal does not belong to the account"
+                );
+
+                self.remove_proposal(&proposal_id);
+                self.proposals.flush();
+            }
+            Action::SetRecoveryConfig(mut config) => {
+                if let Some(ref mut config) = config {
+                    validate_recovery_config(&account_id, config);
+                    config.guardian_epochs = self.guardian_epochs(config);
+                }
+
+                let account = self.accounts.get_mut(&account_id).unwrap();
+                account.recovery_config = config;
+                account.pending_recoveries.clear();
+                account.recovery_nonce += 1;
+            }
+            Action::CancelRecovery => {
+                let account = self.accounts.get_mut(&account_id).unwrap();
+
+                require!(
+                    !account.pending_recoveries.is_empty(),
+                    "No pending recovery"
+                );
+                account.pending_recoveries.clear();
+                account.recovery_nonce += 1;
+            }
+            Action::ApproveRecovery(approval) => {
+                self.record_recovery_approval(Identity::Account(account_id), approval);
+            }
+            Action::SetTimelockPolicy(policy) => {
+                if let Some(ref policy) = policy {
+                    validate_timelock_policy(policy);
+                }
+
+                self.accounts.get_mut(&account_id).unwrap().timelock_policy = policy;
+            }
+            Action::DropToken { token_id } => {
+                require!(
+                    self.accounts
+                        .get_mut(&account_id)
+                        .unwrap()
+                        .drop_token(&token_id),
+                    "Token not held by the account"
+                );
+            }
+            Action::SetSigningPathMode(mode) => {
+                self.accounts
+                    .get_mut(&account_id)
+                    .unwrap()
+                    .signing_path_mode = mode;
+            }
+            Action::CancelPendingOperation(operation_id) => {
+                let account = self.accounts.get_mut(&account_id).unwrap();
+                let pending_operations_count = account.pending_operations.len();
+                account
+                    .pending_operations
+                    .retain(|operation| operation.id != operation_id);
+
+                require!(
+                    account.pending_operations.len() < pending_operations_count,
+                    "Pending operation not found"
+                );
+            }
+            action @ (Action::Withdraw { .. }
+            | Action::FtTransfer { .. }
+            | Action::NftTransfer { .. }) => {
+                self.handle_custody_action(account_id, action);
+            }
+            action @ (Action::CreateSubAccount { .. }
+            | Action::SubAccountFunctionCall { .. }
+            | Action::SubAccountTransfer { .. }
+            | Action::DeleteSubAccount) => {
+                self.handle_sub_account_action(account_id, action);
+            }
             _ => env::panic_str("Invalid account action"),
         }
 
@@ -150,3 +420,139 @@ impl AbstractAccountContract {
             .expect("Storage accounting failed");
     }
 }
+
+impl AbstractAccountContract {
+    /// Reads up to limit accounts from the cursor, collecting the items yielded by each account
+    fn page_accounts<T, I: IntoIterator<Item = T>>(
+        &self,
+        cursor: Option<u32>,
+        limit: Option<u32>,
+        mut account_items: impl FnMut(&AbstractAccountId, &Account) -> I,
+    ) -> Page<T> {
+        let start = cursor.unwrap_or(0);
+        let end = start
+            .saturating_add(limit.unwrap_or(DEFAULT_PAGE_LIMIT))
+            .min(self.accounts.len());
+
+        let items = self
+            .accounts
+            .iter()
+            .skip(start as usize)
+            .take(end.saturating_sub(start) as usize)
+            .flat_map(|(account_id, account)| account_items(account_id, account))
+            .collect();
+
+        Page {
+            items,
+            next_cursor: (end < self.accounts.len()).then_some(end),
+        }
+    }
+
+    fn validate_new_account(&self, predecessor: &AccountId, account_id: &AbstractAccountId) {
+        self.storage_balance_of(predecessor.clone())
+            .unwrap_or_else(|| env::panic_str("Predecessor has not registered for storage"));
+
+        if self.accounts.contains_key(account_id) {
+            env::panic_str("Account already exists");
+        }
+        require!(
+            !self.reserved_account_ids.contains(account_id),
+            "Account id is reserved"
+        );
+    }
+
+    fn create_account(
+        &mut self,
+        predecessor: &AccountId,
+        account_id: AbstractAccountId,
+        identity_with_permissions: IdentityWithPermissions,
+    ) {
+        let storage_usage_start = env::storage_usage();
+        self.account_epoch += 1;
+
+        self.index_identity(&identity_with_permissions.identity, &account_id);
+        self.accounts.insert(
+            account_id,
+            Account::new(vec![identity_with_permissions], self.account_epoch),
+        );
+        self.accounts.flush();
+
+        self.storage_accounting(predecessor, storage_usage_start)
+            .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+    }
+
+    /// Advances the nonce lane of the account. Opening a new lane takes storage, which is charged to the predecessor
+    pub fn consume_nonce(
+        &mut self,
+        predecessor: &AccountId,
+        account_id: &AbstractAccountId,
+        nonce_key: u64,
+    ) {
+        let storage_usage_start = env::storage_usage();
+        let account = self
+            .accounts
+            .get_mut(account_id)
+            .expect("Account not found");
+
+        if account.increment_nonce(nonce_key) {
+            self.storage_balance_of(predecessor.clone())
+                .expect("Predecessor has not registered for storage");
+            self.accounts.flush();
+
+            self.storage_accounting(predecessor, storage_usage_start)
+                .expect("Storage accounting failed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::test_utils::{
+        full_access, insert_account, register_storage, setup, setup_account, CONTRACT_ID,
+    };
+    use near_sdk::json_types::U128;
+
+    #[test]
+    #[should_panic(expected = "Account id is reserved")]
+    fn validate_new_account_should_reject_reserved_ids_whatever_their_case() {
+        let mut contract = setup();
+        register_storage(&mut contract, CONTRACT_ID);
+        contract.reserve_account_ids(vec!["Brand".parse().unwrap()]);
+
+        contract.validate_new_account(&CONTRACT_ID.parse().unwrap(), &"BRAND".parse().unwrap());
+    }
+
+    #[test]
+    fn validate_new_account_should_accept_released_ids() {
+        let mut contract = setup();
+        register_storage(&mut contract, CONTRACT_ID);
+        contract.reserve_account_ids(vec!["brand".parse().unwrap()]);
+        contract.release_account_ids(vec!["brand".parse().unwrap()]);
+
+        assert!(!contract.is_account_id_reserved("brand".parse().unwrap()));
+        contract.validate_new_account(&CONTRACT_ID.parse().unwrap(), &"brand".parse().unwrap());
+    }
+
+    #[test]
+    fn consume_nonce_should_only_advance_its_lane() {
+        let (mut contract, account_id) = setup_account(vec![full_access("bob")]);
+
+        contract.consume_nonce(&CONTRACT_ID.parse().unwrap(), &account_id, 0);
+        contract.consume_nonce(&CONTRACT_ID.parse().unwrap(), &account_id, 7);
+        contract.consume_nonce(&CONTRACT_ID.parse().unwrap(), &account_id, 7);
+
+        assert_eq!(contract.get_nonce(account_id.clone(), 0), Some(U128(1)));
+        assert_eq!(contract.get_nonce(account_id.clone(), 7), Some(U128(2)));
+        assert_eq!(contract.get_nonce(account_id, 3), Some(U128(0)));
+        assert_eq!(contract.get_nonce("bob".parse().unwrap(), 0), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Predecessor has not registered for storage")]
+    fn consume_nonce_should_charge_the_storage_of_a_new_lane() {
+        let mut contract = setup();
+        let account_id = insert_account(&mut contract, "alice", vec![full_access("bob")]);
+
+        contract.consume_nonce(&CONTRACT_ID.parse().unwrap(), &account_id, 1);
+    }
+}
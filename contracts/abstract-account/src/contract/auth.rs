@@ -3,6 +3,7 @@ use crate::mods::external_contracts::{
     VALIDATE_P256_SIGNATURE_GAS,
 };
 use crate::types::identity::{AuthTypeNames, Identity};
+use crate::types::transaction::ValidUntil;
 use crate::*;
 use base64::engine::{general_purpose::URL_SAFE_NO_PAD, Engine};
 use interfaces::auth::{
@@ -10,6 +11,7 @@ use interfaces::auth::{
     wallet::{WalletCredentials, WalletValidationData},
     webauthn::{WebAuthnCredentials, WebAuthnValidationData},
 };
+use interfaces::traits::signable_message::EncodedMessage;
 use near_sdk::{env, require, Promise};
 use serde_json::Value;
 use utils::utils::parse_credentials;
@@ -18,18 +20,18 @@ impl AbstractAccountContract {
     fn get_auth_contract(&self, name: &AuthTypeNames) -> AccountId {
         self.auth_contracts
             .get(name)
-            .expect(&format!("{:?} contract not configured", name))
+            .unwrap_or_else(|| env::panic_str(&format!("{:?} contract not configured", name)))
             .clone()
     }
 
     pub fn handle_oidc_auth(
         &self,
         credentials: OIDCCredentials,
-        signed_message: String,
+        signed_message: EncodedMessage,
         oidc_authenticator: OIDCAuthenticator,
     ) -> Promise {
         let oidc_data = OIDCValidationData {
-            message: signed_message,
+            message: signed_message.to_text(),
             token: credentials.token,
         };
 
@@ -37,19 +39,18 @@ impl AbstractAccountContract {
 
         oidc_auth::ext(oidc_contract)
             .with_static_gas(VALIDATE_ETH_SIGNATURE_GAS)
-            .with_attached_deposit(env::attached_deposit())
             .verify(oidc_data, oidc_authenticator)
     }
 
     pub fn handle_wallet_auth(
         &self,
         credentials: WalletCredentials,
-        signed_message: String,
+        signed_message: EncodedMessage,
         compressed_public_key: String,
         wallet_type: AuthTypeNames,
     ) -> Promise {
         let wallet_data = WalletValidationData {
-            message: signed_message,
+            message: signed_message.to_text(),
             signature: credentials.signature,
         };
 
@@ -58,11 +59,9 @@ impl AbstractAccountContract {
         match wallet_type {
             AuthTypeNames::EthereumWallet => ethereum_auth::ext(contract)
                 .with_static_gas(VALIDATE_ETH_SIGNATURE_GAS)
-                .with_attached_deposit(env::attached_deposit())
                 .verify(wallet_data, compressed_public_key),
             AuthTypeNames::SolanaWallet => solana_auth::ext(contract)
                 .with_static_gas(VALIDATE_ETH_SIGNATURE_GAS)
-                .with_attached_deposit(env::attached_deposit())
                 .verify(wallet_data, compressed_public_key),
             _ => env::panic_str("Invalid wallet type"),
         }
@@ -71,7 +70,7 @@ impl AbstractAccountContract {
     pub fn handle_webauthn_auth(
         &self,
         credentials: WebAuthnCredentials,
-        signed_message: String,
+        signed_message: EncodedMessage,
         compressed_public_key: String,
     ) -> Promise {
         let client_data: serde_json::Value =
@@ -80,8 +79,8 @@ impl AbstractAccountContract {
         let client_challenge = client_data["challenge"]
             .as_str()
             .expect("Missing challenge in client data");
-        // TODO: Remove hashing later
-        let signed_message = URL_SAFE_NO_PAD.encode(env::sha256(signed_message.as_bytes()));
+        // The challenge carries the encoded message bytes, the SHA-256 digest by default
+        let signed_message = URL_SAFE_NO_PAD.encode(&signed_message.bytes);
 
         require!(
             client_challenge == signed_message,
@@ -101,7 +100,6 @@ impl AbstractAccountContract {
 
         webauthn_auth::ext(webauthn_contract)
             .with_static_gas(VALIDATE_P256_SIGNATURE_GAS)
-            .with_attached_deposit(env::attached_deposit())
             .verify_p256(webauthn_data, compressed_public_key)
     }
 
@@ -110,8 +108,7 @@ impl AbstractAccountContract {
     /// # Arguments
     /// * `identity` - The authentication method being used
     /// * `credentials` - The credentials provided for authentication
-    /// * `signed_message` - The message that was signed authorizing the execution
-    /// * `account` - The account being authenticated against
+    /// * `signed_message` - The message that was signed authorizing the execution, in the encoding selected by the signer
     /// * `authenticate_callback` - Promise to execute if authentication succeeds
     ///
     /// # Returns
@@ -120,10 +117,48 @@ impl AbstractAccountContract {
         &self,
         identity: Identity,
         credentials: Value,
-        signed_message: String,
+        signed_message: EncodedMessage,
         authenticate_callback: Promise,
     ) -> Promise {
-        let promise = match identity {
+        self.verify_credentials(identity, credentials, signed_message)
+            .then(authenticate_callback)
+    }
+
+    /// Validates several credentials in a single joined promise
+    ///
+    /// # Arguments
+    /// * `auths` - The identities, their credentials and the messages they signed, in the order their results are passed to the callback
+    /// * `authenticate_callback` - Promise receiving one boolean result per identity
+    pub fn validate_batch_credentials(
+        &self,
+        auths: Vec<(Identity, Value, EncodedMessage)>,
+        authenticate_callback: Promise,
+    ) -> Promise {
+        self.verify_batch_credentials(auths)
+            .unwrap_or_else(|| env::panic_str("At least one credential is required"))
+            .then(authenticate_callback)
+    }
+
+    /// Joins the verifications of several credentials, None if there are no credentials
+    pub fn verify_batch_credentials(
+        &self,
+        auths: Vec<(Identity, Value, EncodedMessage)>,
+    ) -> Option<Promise> {
+        auths
+            .into_iter()
+            .map(|(identity, credentials, signed_message)| {
+                self.verify_credentials(identity, credentials, signed_message)
+            })
+            .reduce(|joined, promise| joined.and(promise))
+    }
+
+    fn verify_credentials(
+        &self,
+        identity: Identity,
+        credentials: Value,
+        signed_message: EncodedMessage,
+    ) -> Promise {
+        match identity {
             Identity::WebAuthn(webauthn) => {
                 let credentials = parse_credentials(&credentials);
                 if let Some(compressed_public_key) = webauthn.compressed_public_key {
@@ -138,11 +173,8 @@ impl AbstractAccountContract {
                     );
                 }
             }
-            Identity::Wallet(wallet) => {
-                let wallet_type = match wallet.wallet_type {
-                    WalletType::Ethereum => AuthTypeNames::EthereumWallet,
-                    WalletType::Solana => AuthTypeNames::SolanaWallet,
-                };
+            Identity::Wallet(ref wallet) => {
+                let wallet_type = identity.auth_type();
 
                 let credentials = parse_credentials(&credentials);
 
@@ -159,32 +191,65 @@ impl AbstractAccountContract {
                 self.handle_oidc_auth(credentials, signed_message, oidc)
             }
             Identity::Account(_) => env::panic_str("Account auth type not yet supported"),
-        };
-
-        promise.then(authenticate_callback)
+        }
     }
 
     /// Validates that the user operation has valid permissions and account details
     ///
     /// # Arguments
     /// * `user_op` - The user operation to validate
+    /// * `is_pending` - Whether the transaction is a pending proposal, whose nonce was consumed when it was proposed
     ///
     /// # Panics
-    /// * If the auth identity is not found in the account
-    /// * If the nonce doesn't match the account nonce
+    /// * If the transaction has no actions or a Sign action is not signable by its signer
+    /// * If an auth identity is not found in the account or appears more than once
+    /// * If the epoch or nonce doesn't match the account ones
+    /// * If the transaction valid_until deadline has passed
     /// * If acting as another identity without proper permissions
-    pub fn validate_permission_and_account(&self, user_op: &UserOp) {
+    pub fn validate_permission_and_account(&self, user_op: &UserOp, is_pending: bool) {
         let account = self
             .accounts
             .get(&user_op.transaction.account_id)
             .expect("Account not found");
 
         require!(
-            account.get_identity(&user_op.auth.identity).is_some(),
-            "Auth identity not found in account"
+            !user_op.transaction.actions.is_empty(),
+            "Transaction must contain at least one action"
         );
+        self.validate_sign_requests(&user_op.transaction);
+
+        let auths = user_op.auth.auths();
+        require!(!auths.is_empty(), "At least one auth is required");
 
-        require!(account.nonce == user_op.transaction.nonce, "Nonce mismatch");
+        for (index, auth) in auths.iter().enumerate() {
+            require!(
+                account.get_identity(&auth.identity).is_some(),
+                "Auth identity not found in account"
+            );
+            require!(
+                auths[..index]
+                    .iter()
+                    .all(|other| other.identity != auth.identity),
+                "Duplicated auth identity"
+            );
+        }
+
+        require!(
+            account.epoch == user_op.transaction.epoch,
+            "Account epoch mismatch"
+        );
+        require!(
+            !user_op
+                .transaction
+                .valid_until
+                .as_ref()
+                .is_some_and(ValidUntil::is_expired),
+            "Transaction expired"
+        );
+        require!(
+            is_pending || account.nonce(user_op.transaction.nonce_key) == user_op.transaction.nonce,
+            "Nonce mismatch"
+        );
 
         if let Some(ref act_as) = user_op.act_as {
             let identity = account
@@ -199,3 +264,96 @@ impl AbstractAccountContract {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::transaction::{Auth, UserOpAuth};
+    use crate::utils::test_utils::{
+        context, full_access, identity, setup_account, transaction, CONTRACT_ID,
+    };
+    use near_sdk::{json_types::U64, testing_env};
+    use serde_json::json;
+
+    fn user_op(transaction: Transaction) -> UserOp {
+        UserOp {
+            auth: UserOpAuth::Single(Auth {
+                identity: identity("bob"),
+                credentials: json!({}),
+                encoding: None,
+            }),
+            act_as: None,
+            transaction,
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Account epoch mismatch")]
+    fn validate_permission_and_account_should_reject_the_epoch_of_a_removed_account() {
+        let (mut contract, account_id) = setup_account(vec![full_access("bob")]);
+        let user_op = user_op(transaction(&account_id, vec![Action::RemoveAccount]));
+
+        // The recreated account starts again at nonce 0, only its epoch differs
+        contract.remove_account(account_id.clone());
+        contract.add_account(account_id.clone(), full_access("bob"), None);
+        assert_eq!(contract.get_nonce(account_id, 0), Some(U128(0)));
+
+        contract.validate_permission_and_account(&user_op, false);
+    }
+
+    #[test]
+    fn validate_permission_and_account_should_check_the_nonce_of_the_lane() {
+        let (mut contract, account_id) = setup_account(vec![full_access("bob")]);
+        contract.consume_nonce(&CONTRACT_ID.parse().unwrap(), &account_id, 0);
+
+        // Lane 1 was never used, so it still expects nonce 0
+        let mut transaction = transaction(&account_id, vec![Action::RemoveAccount]);
+        transaction.nonce_key = 1;
+        contract.validate_permission_and_account(&user_op(transaction), false);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nonce mismatch")]
+    fn validate_permission_and_account_should_reject_a_used_nonce_of_the_lane() {
+        let (mut contract, account_id) = setup_account(vec![full_access("bob")]);
+        contract.consume_nonce(&CONTRACT_ID.parse().unwrap(), &account_id, 0);
+
+        let transaction = transaction(&account_id, vec![Action::RemoveAccount]);
+        contract.validate_permission_and_account(&user_op(transaction), false);
+    }
+
+    #[test]
+    fn validate_permission_and_account_should_accept_a_transaction_until_its_deadline() {
+        let (contract, account_id) = setup_account(vec![full_access("bob")]);
+        testing_env!(context().block_timestamp(100).block_height(10).build());
+
+        let mut transaction = transaction(&account_id, vec![Action::RemoveAccount]);
+        transaction.valid_until = Some(ValidUntil::Timestamp(U64(100)));
+        contract.validate_permission_and_account(&user_op(transaction.clone()), false);
+
+        transaction.valid_until = Some(ValidUntil::BlockHeight(U64(10)));
+        contract.validate_permission_and_account(&user_op(transaction), false);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transaction expired")]
+    fn validate_permission_and_account_should_reject_a_transaction_past_its_timestamp() {
+        let (contract, account_id) = setup_account(vec![full_access("bob")]);
+        testing_env!(context().block_timestamp(101).build());
+
+        let mut transaction = transaction(&account_id, vec![Action::RemoveAccount]);
+        transaction.valid_until = Some(ValidUntil::Timestamp(U64(100)));
+        contract.validate_permission_and_account(&user_op(transaction), false);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transaction expired")]
+    fn validate_permission_and_account_should_reject_a_transaction_past_its_block_height() {
+        let (contract, account_id) = setup_account(vec![full_access("bob")]);
+        testing_env!(context().block_height(11).build());
+
+        let mut transaction = transaction(&account_id, vec![Action::RemoveAccount]);
+        transaction.valid_until = Some(ValidUntil::BlockHeight(U64(10)));
+        contract.validate_permission_and_account(&user_op(transaction), false);
+    }
+}
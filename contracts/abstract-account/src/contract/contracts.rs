@@ -12,4 +12,9 @@ impl AbstractAccountContract {
     pub fn get_signer_account(&self) -> String {
         self.signer_account.clone().into()
     }
+
+    /// Domain that must be included in every message signed in the given encoding
+    pub fn get_domain(&self, encoding: MessageEncoding) -> Domain {
+        self.domain(encoding)
+    }
 }
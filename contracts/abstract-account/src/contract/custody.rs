@@ -0,0 +1,322 @@
+use crate::mods::tokens::{
+    ext_ft, ext_nft, CUSTODY_CALLBACK_GAS, FT_TRANSFER_GAS, NFT_TRANSFER_GAS,
+};
+use crate::types::custody::CustodyAsset;
+use crate::*;
+use near_sdk::{
+    env, json_types::U128, require, AccountId, NearToken, Promise, PromiseOrValue, PromiseResult,
+};
+use near_sdk_contract_tools::nft::nep145::{Nep145, Nep145Controller};
+
+#[near]
+impl AbstractAccountContract {
+    /// NEP-141 receiver crediting the tokens to the account in msg. The token contract pays for the storage used
+    pub fn ft_on_transfer(
+        &mut self,
+        #[allow(unused_variables)] sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        self.receive_asset(
+            msg.parse().unwrap_or_else(|error| env::panic_str(error)),
+            CustodyAsset::FungibleToken {
+                token_id: env::predecessor_account_id().to_string(),
+                amount,
+            },
+        );
+
+        PromiseOrValue::Value(U128(0))
+    }
+
+    /// NEP-171 receiver crediting the token to the account in msg. The token contract pays for the storage used
+    pub fn nft_on_transfer(
+        &mut self,
+        #[allow(unused_variables)] sender_id: AccountId,
+        #[allow(unused_variables)] previous_owner_id: AccountId,
+        token_id: String,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        self.receive_asset(
+            msg.parse().unwrap_or_else(|error| env::panic_str(error)),
+            CustodyAsset::NonFungibleToken {
+                token_id: env::predecessor_account_id().to_string(),
+                nft_id: token_id,
+            },
+        );
+
+        PromiseOrValue::Value(false)
+    }
+
+    /// Credits the asset back to the account if the external action moving it failed
+    #[private]
+    pub fn custody_callback(&mut self, account_id: AbstractAccountId, asset: CustodyAsset) {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return;
+        }
+
+        // Failed receipts return their deposit and tokens to this contract
+        if let Some(account) = self.accounts.get_mut(&account_id) {
+            account.credit(asset);
+        }
+    }
+}
+
+impl AbstractAccountContract {
+    /// Credits the asset to the account, paying the storage it uses from the NEP-145 storage balance of the token contract.
+    /// Anyone can call the receivers, so neither the account nor the claimed sender is charged: tokens nobody asked for
+    /// would drain them. Anyone can fund the storage balance of a token contract through storage_deposit
+    fn receive_asset(&mut self, account_id: AbstractAccountId, asset: CustodyAsset) {
+        let storage_usage_start = env::storage_usage();
+        let token_contract = env::predecessor_account_id();
+
+        // Panicking makes the token contract return the tokens to the sender
+        self.storage_balance_of(token_contract.clone())
+            .unwrap_or_else(|| env::panic_str("Token contract has not registered for storage"));
+
+        self.accounts
+            .get_mut(&account_id)
+            .expect("Account not found")
+            .credit(asset);
+        self.accounts.flush();
+
+        self.storage_accounting(&token_contract, storage_usage_start)
+            .unwrap_or_else(|e| env::panic_str(&e.to_string()));
+    }
+
+    /// Moves an asset out of custody, crediting it back if the external action fails
+    pub fn handle_custody_action(&mut self, account_id: AbstractAccountId, action: Action) {
+        let (promise, asset) = match action {
+            Action::Withdraw {
+                receiver_id,
+                amount,
+            } => (
+                Promise::new(self.validate_receiver(&account_id, &receiver_id))
+                    .transfer(NearToken::from_yoctonear(amount.0)),
+                CustodyAsset::Near(amount),
+            ),
+            Action::FtTransfer {
+                token_id,
+                receiver_id,
+                amount,
+                memo,
+            } => (
+                ext_ft::ext(token_id.parse().expect("Invalid token_id"))
+                    .with_attached_deposit(NearToken::from_yoctonear(1))
+                    .with_static_gas(FT_TRANSFER_GAS)
+                    .ft_transfer(
+                        self.validate_receiver(&account_id, &receiver_id),
+                        amount,
+                        memo,
+                    ),
+                CustodyAsset::FungibleToken { token_id, amount },
+            ),
+            Action::NftTransfer {
+                token_id,
+                nft_id,
+                receiver_id,
+                memo,
+            } => (
+                ext_nft::ext(token_id.parse().expect("Invalid token_id"))
+                    .with_attached_deposit(NearToken::from_yoctonear(1))
+                    .with_static_gas(NFT_TRANSFER_GAS)
+                    .nft_transfer(
+                        self.validate_receiver(&account_id, &receiver_id),
+                        nft_id.clone(),
+                        None,
+                        memo,
+                    ),
+                CustodyAsset::NonFungibleToken { token_id, nft_id },
+            ),
+            _ => env::panic_str("Invalid custody action"),
+        };
+
+        self.accounts.get_mut(&account_id).unwrap().debit(&asset);
+
+        promise.then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(CUSTODY_CALLBACK_GAS)
+                .custody_callback(account_id, asset),
+        );
+    }
+
+    /// Blocks receivers that would let an account act with the contract privileges:
+    /// the contract itself, whose private methods accept its own calls, the sub-accounts of other accounts, whose proxies
+    /// are owned by the contract and hold their funds, and the signers, which derive the keys of every account.
+    /// The proxy of the account itself is allowed, so it can be funded from custody
+    fn validate_receiver(&self, account_id: &AbstractAccountId, receiver_id: &str) -> AccountId {
+        let receiver_id: AccountId = receiver_id.parse().expect("Invalid receiver_id");
+        let current_account_id = env::current_account_id();
+        let own_sub_account = self
+            .accounts
+            .get(account_id)
+            .unwrap()
+            .sub_account
+            .as_deref()
+            == Some(receiver_id.as_str());
+
+        require!(
+            receiver_id != current_account_id
+                && (own_sub_account || !receiver_id.is_sub_account_of(&current_account_id))
+                && !self.signers.contains_key(&receiver_id),
+            "Receiver not allowed"
+        );
+
+        receiver_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::{context, full_access, register_storage, setup_account};
+    use near_sdk::testing_env;
+
+    // An account holding 1 NEAR
+    fn funded_account() -> (AbstractAccountContract, AbstractAccountId) {
+        let (mut contract, account_id) = setup_account(vec![full_access("bob")]);
+        contract.accounts.get_mut(&account_id).unwrap().balance =
+            U128(NearToken::from_near(1).as_yoctonear());
+
+        (contract, account_id)
+    }
+
+    fn withdraw(receiver_id: &str) -> Action {
+        Action::Withdraw {
+            receiver_id: receiver_id.to_string(),
+            amount: U128(1),
+        }
+    }
+
+    #[test]
+    fn withdraw_should_reach_external_accounts() {
+        let (mut contract, account_id) = funded_account();
+
+        contract.handle_custody_action(account_id.clone(), withdraw("bob.near"));
+
+        assert_eq!(
+            contract.accounts.get(&account_id).unwrap().balance.0,
+            NearToken::from_near(1).as_yoctonear() - 1
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Receiver not allowed")]
+    fn withdraw_should_fail_for_other_account_proxy() {
+        let (mut contract, account_id) = funded_account();
+
+        contract.handle_custody_action(account_id, withdraw("victim.abstract.near"));
+    }
+
+    #[test]
+    fn withdraw_should_reach_own_proxy() {
+        let (mut contract, account_id) = funded_account();
+        contract.accounts.get_mut(&account_id).unwrap().sub_account =
+            Some("alice.abstract.near".to_string());
+
+        contract.handle_custody_action(account_id.clone(), withdraw("alice.abstract.near"));
+
+        assert_eq!(
+            contract.accounts.get(&account_id).unwrap().balance.0,
+            NearToken::from_near(1).as_yoctonear() - 1
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Receiver not allowed")]
+    fn withdraw_should_fail_for_contract() {
+        let (mut contract, account_id) = funded_account();
+
+        contract.handle_custody_action(account_id, withdraw("abstract.near"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Receiver not allowed")]
+    fn withdraw_should_fail_for_signer() {
+        let (mut contract, account_id) = funded_account();
+
+        contract.handle_custody_action(account_id, withdraw("signer.near"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Receiver not allowed")]
+    fn ft_transfer_should_fail_for_other_account_proxy() {
+        let (mut contract, account_id) = funded_account();
+        contract
+            .accounts
+            .get_mut(&account_id)
+            .unwrap()
+            .credit(CustodyAsset::FungibleToken {
+                token_id: "token.near".to_string(),
+                amount: U128(10),
+            });
+
+        contract.handle_custody_action(
+            account_id,
+            Action::FtTransfer {
+                token_id: "token.near".to_string(),
+                receiver_id: "victim.abstract.near".to_string(),
+                amount: U128(10),
+                memo: None,
+            },
+        );
+    }
+
+    #[test]
+    fn ft_on_transfer_should_charge_storage_to_the_token_contract() {
+        let (mut contract, account_id) = funded_account();
+        register_storage(&mut contract, "token.near");
+        testing_env!(context()
+            .predecessor_account_id("token.near".parse().unwrap())
+            .build());
+
+        contract.ft_on_transfer(
+            "sender.near".parse().unwrap(),
+            U128(10),
+            account_id.to_string(),
+        );
+
+        let account = contract.accounts.get(&account_id).unwrap();
+        assert_eq!(account.fungible_tokens.get("token.near"), Some(&U128(10)));
+        assert_eq!(account.balance.0, NearToken::from_near(1).as_yoctonear());
+
+        let storage_balance = contract
+            .storage_balance_of("token.near".parse().unwrap())
+            .unwrap();
+        assert!(storage_balance.available < NearToken::from_near(1));
+    }
+
+    #[test]
+    fn drop_token_should_stop_blocking_account_removal() {
+        let (mut contract, account_id) = funded_account();
+        let account = contract.accounts.get_mut(&account_id).unwrap();
+        account.credit(CustodyAsset::FungibleToken {
+            token_id: "spam.near".to_string(),
+            amount: U128(10),
+        });
+        account.credit(CustodyAsset::NonFungibleToken {
+            token_id: "spam.near".to_string(),
+            nft_id: "1".to_string(),
+        });
+        assert!(account.holds_tokens());
+
+        assert!(account.drop_token("spam.near"));
+        assert!(!account.holds_tokens());
+        assert!(!account.drop_token("spam.near"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Token contract has not registered for storage")]
+    fn nft_on_transfer_should_fail_without_token_contract_storage() {
+        let (mut contract, account_id) = funded_account();
+        testing_env!(context()
+            .predecessor_account_id("nft.near".parse().unwrap())
+            .build());
+
+        contract.nft_on_transfer(
+            "sender.near".parse().unwrap(),
+            "sender.near".parse().unwrap(),
+            "1".to_string(),
+            account_id.to_string(),
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use crate::types::view::DEFAULT_PAGE_LIMIT;
+use crate::*;
+
+#[near]
+impl AbstractAccountContract {
+    /// Accounts the identity belongs to, read from the identity index
+    ///
+    /// # Arguments
+    /// * `identity` - The identity to look up, WebAuthn identities can omit the public key
+    /// * `from_index` - Position of the first account in the identity index, 0 when omitted
+    /// * `limit` - Maximum number of accounts read from the index
+    pub fn get_account_by_identity(
+        &self,
+        identity: Identity,
+        from_index: Option<u32>,
+        limit: Option<u32>,
+    ) -> Vec<AbstractAccountId> {
+        let Some(account_ids) = self.identity_accounts.get(&identity.index_key()) else {
+            return vec![];
+        };
+
+        account_ids
+            .iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(DEFAULT_PAGE_LIMIT) as usize)
+            // WebAuthn identities with the same key_id and different public keys share the index entry
+            .filter(|account_id| {
+                self.accounts
+                    .get(*account_id)
+                    .is_some_and(|account| account.get_identity(&identity).is_some())
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Number of entries of the identity index, to paginate get_account_by_identity
+    pub fn get_account_count_by_identity(&self, identity: Identity) -> u32 {
+        self.identity_accounts
+            .get(&identity.index_key())
+            .map_or(0, |account_ids| account_ids.len())
+    }
+}
+
+impl AbstractAccountContract {
+    /// Adds the account to the identity index. The storage is paid by the caller storage accounting
+    pub fn index_identity(&mut self, identity: &Identity, account_id: &AbstractAccountId) {
+        let key = identity.index_key();
+        let account_ids = self.identity_accounts.entry(key).or_insert_with(|| {
+            IterableSet::new([KEY_PREFIX_IDENTITY_ACCOUNT_IDS, key.as_slice()].concat())
+        });
+
+        account_ids.insert(account_id.clone());
+        account_ids.flush();
+        self.identity_accounts.flush();
+    }
+
+    /// Removes the account from the identity index once the account no longer has an identity with its key
+    pub fn unindex_identity(&mut self, identity: &Identity, account_id: &AbstractAccountId) {
+        let key = identity.index_key();
+
+        // The account may keep an identity with the same key, e.g. the same WebAuthn key_id with another public key
+        if self.accounts.get(account_id).is_some_and(|account| {
+            account
+                .identities
+                .iter()
+                .any(|current| current.identity.index_key() == key)
+        }) {
+            return;
+        }
+
+        let Some(account_ids) = self.identity_accounts.get_mut(&key) else {
+            return;
+        };
+
+        account_ids.remove(account_id);
+        account_ids.flush();
+
+        if account_ids.is_empty() {
+            self.identity_accounts.remove(&key);
+        }
+        self.identity_accounts.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::{full_access, identity, setup_account};
+    use interfaces::auth::webauthn::WebAuthnAuthenticator;
+
+    fn passkey(compressed_public_key: Option<&str>) -> Identity {
+        Identity::WebAuthn(WebAuthnAuthenticator {
+            key_id: "passkey".to_string(),
+            compressed_public_key: compressed_public_key.map(str::to_string),
+        })
+    }
+
+    fn add_identity(
+        contract: &mut AbstractAccountContract,
+        account_id: &AbstractAccountId,
+        identity: Identity,
+    ) {
+        contract.add_identity(
+            account_id.clone(),
+            IdentityWithPermissions {
+                identity,
+                permissions: None,
+            },
+            Ok(true),
+        );
+    }
+
+    #[test]
+    fn add_and_remove_identity_should_update_the_index() {
+        let (mut contract, account_id) = setup_account(vec![full_access("bob")]);
+
+        add_identity(&mut contract, &account_id, identity("carol"));
+        assert_eq!(
+            contract.get_account_by_identity(identity("carol"), None, None),
+            vec![account_id.clone()]
+        );
+
+        contract.remove_identity(account_id.clone(), &identity("carol"));
+        assert!(contract
+            .get_account_by_identity(identity("carol"), None, None)
+            .is_empty());
+        assert_eq!(contract.get_account_count_by_identity(identity("carol")), 0);
+        assert_eq!(contract.get_account_count_by_identity(identity("bob")), 1);
+    }
+
+    #[test]
+    fn remove_account_should_only_unindex_its_identities() {
+        let (mut contract, alice) = setup_account(vec![full_access("bob")]);
+        let dave: AbstractAccountId = "dave".parse().unwrap();
+        contract.add_account(dave.clone(), full_access("bob"), None);
+        add_identity(&mut contract, &alice, identity("carol"));
+
+        contract.remove_account(alice);
+
+        assert_eq!(
+            contract.get_account_by_identity(identity("bob"), None, None),
+            vec![dave]
+        );
+        assert_eq!(contract.get_account_count_by_identity(identity("carol")), 0);
+    }
+
+    #[test]
+    fn unindex_identity_should_keep_an_account_with_another_public_key_of_the_key_id() {
+        let (mut contract, account_id) = setup_account(vec![full_access("bob")]);
+        add_identity(&mut contract, &account_id, passkey(Some("key-1")));
+        add_identity(&mut contract, &account_id, passkey(Some("key-2")));
+
+        // Both public keys share the index entry of the key_id
+        assert_eq!(contract.get_account_count_by_identity(passkey(None)), 1);
+
+        contract.remove_identity(account_id.clone(), &passkey(Some("key-1")));
+        assert_eq!(
+            contract.get_account_by_identity(passkey(None), None, None),
+            vec![account_id.clone()]
+        );
+        assert!(contract
+            .get_account_by_identity(passkey(Some("key-1")), None, None)
+            .is_empty());
+
+        contract.remove_identity(account_id, &passkey(Some("key-2")));
+        assert_eq!(contract.get_account_count_by_identity(passkey(None)), 0);
+    }
+}
@@ -0,0 +1,398 @@
+use near_sdk::env;
+use near_sdk_contract_tools::nft::nep145::{Nep145, Nep145Controller};
+
+use crate::*;
+
+#[near]
+impl AbstractAccountContract {
+    pub fn get_proposal(&self, proposal_id: String) -> Option<&Proposal> {
+        self.proposals.get(&proposal_id)
+    }
+
+    /// Pending proposals of the account, read from the account proposal index. Expired ones are skipped
+    pub fn list_proposals(&self, account_id: AbstractAccountId) -> Vec<(String, Proposal)> {
+        let Some(proposal_ids) = self.account_proposals.get(&account_id) else {
+            return vec![];
+        };
+
+        proposal_ids
+            .iter()
+            .filter_map(|proposal_id| {
+                self.proposals
+                    .get(proposal_id)
+                    .filter(|proposal| !proposal.is_expired())
+                    .map(|proposal| (proposal_id.clone(), proposal.clone()))
+            })
+            .collect()
+    }
+}
+
+impl AbstractAccountContract {
+    /// Records the approvals of full access identities on the proposal of the given transaction
+    ///
+    /// # Arguments
+    /// * `predecessor` - The account paying for the proposal storage
+    /// * `signers` - The identities that authenticated the approval
+    /// * `act_as` - The identity the transaction will be executed as
+    /// * `transaction` - The transaction being approved
+    /// * `is_pending` - Whether the proposal already existed when the approval was submitted
+    ///
+    /// # Returns
+    /// The proposal, removed from the pending ones, once it gathered enough approvals to be executed.
+    /// An error if the approval is rejected, in which case nothing is recorded
+    pub fn approve_proposal(
+        &mut self,
+        predecessor: &AccountId,
+        signers: Vec<Identity>,
+        act_as: Option<Identity>,
+        transaction: Transaction,
+        is_pending: bool,
+    ) -> Result<Option<Proposal>, String> {
+        let storage_usage_start = env::storage_usage();
+        if self.storage_balance_of(predecessor.clone()).is_none() {
+            return Err("Predecessor has not registered for storage".to_string());
+        }
+
+        let account = self
+            .accounts
+            .get(&transaction.account_id)
+            .expect("Account not found");
+
+        if !signers.iter().all(|signer| account.has_full_access(signer)) {
+            return Err("Only full access identities can approve proposals".to_string());
+        }
+
+        let threshold = account.required_approvals(&transaction.actions);
+        let proposal_id = transaction.hash();
+        let account_id = transaction.account_id.clone();
+
+        if is_pending {
+            // The proposal may have been executed, cancelled or expired while this approval was being authenticated
+            let Some(proposal) = self
+                .proposals
+                .get(&proposal_id)
+                .filter(|proposal| !proposal.is_expired())
+            else {
+                return Err("Proposal is no longer pending".to_string());
+            };
+
+            if proposal.act_as != act_as {
+                return Err("act_as does not match the pending proposal".to_string());
+            }
+            if signers.iter().any(|signer| proposal.is_approved_by(signer)) {
+                return Err("Identity already approved the proposal".to_string());
+            }
+        } else {
+            // Expired proposals of the account are pruned whenever a new one is recorded, so they don't accumulate
+            self.remove_expired_proposals(&account_id);
+            self.insert_proposal(
+                proposal_id.clone(),
+                Proposal::new(act_as.clone(), transaction),
+            );
+        }
+
+        let account = self.accounts.get(&account_id).unwrap();
+        let proposal = self.proposals.get_mut(&proposal_id).unwrap();
+        proposal.approvals.extend(signers);
+
+        // Approvals from identities removed since then, or downgraded from full access, no longer count
+        let approvals = proposal
+            .approvals
+            .iter()
+            .filter(|approval| account.has_full_access(approval))
+            .count() as u32;
+
+        let approved_proposal = if approvals >= threshold {
+            self.remove_proposal(&proposal_id)
+        } else {
+            None
+        };
+
+        self.proposals.flush();
+
+        self.storage_accounting(predecessor, storage_usage_start)
+            .expect("Storage accounting failed");
+
+        Ok(approved_proposal)
+    }
+
+    /// Adds the proposal to the account proposal index. The storage is paid by the caller storage accounting
+    fn insert_proposal(&mut self, proposal_id: String, proposal: Proposal) {
+        let account_id = proposal.transaction.account_id.clone();
+        let proposal_ids = self
+            .account_proposals
+            .entry(account_id.clone())
+            .or_insert_with(|| {
+                // Hashed so account ids that prefix each other don't share storage keys
+                let key = env::sha256(account_id.as_str().as_bytes());
+                IterableSet::new([KEY_PREFIX_ACCOUNT_PROPOSAL_IDS, key.as_slice()].concat())
+            });
+
+        proposal_ids.insert(proposal_id.clone());
+        proposal_ids.flush();
+        self.account_proposals.flush();
+
+        self.proposals.insert(proposal_id, proposal);
+    }
+
+    /// Removes the proposal and its account proposal index entry
+    pub fn remove_proposal(&mut self, proposal_id: &String) -> Option<Proposal> {
+        let proposal = self.proposals.remove(proposal_id)?;
+        let account_id = &proposal.transaction.account_id;
+
+        if let Some(proposal_ids) = self.account_proposals.get_mut(account_id) {
+            proposal_ids.remove(proposal_id);
+            proposal_ids.flush();
+
+            if proposal_ids.is_empty() {
+                self.account_proposals.remove(account_id);
+            }
+            self.account_proposals.flush();
+        }
+
+        Some(proposal)
+    }
+
+    /// Removes the proposals of the account that can no longer be approved
+    pub fn remove_expired_proposals(&mut self, account_id: &AbstractAccountId) {
+        self.remove_account_proposals(account_id, true);
+    }
+
+    /// Removes the proposals of the account, only the expired ones if `expired_only`
+    pub fn remove_account_proposals(&mut self, account_id: &AbstractAccountId, expired_only: bool) {
+        let Some(proposal_ids) = self.account_proposals.get(account_id) else {
+            return;
+        };
+
+        let removed: Vec<String> = proposal_ids
+            .iter()
+            .filter(|proposal_id| {
+                !expired_only
+                    || self
+                        .proposals
+                        .get(*proposal_id)
+                        .is_none_or(|proposal| proposal.is_expired())
+            })
+            .cloned()
+            .collect();
+
+        for proposal_id in removed {
+            self.remove_proposal(&proposal_id);
+        }
+        self.proposals.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::identity::IdentityPermissions;
+    use crate::types::multisig::{MultisigPolicy, PROPOSAL_TTL};
+    use crate::types::transaction::ActionKind;
+    use crate::utils::test_utils::{
+        context, full_access, identity, setup_account, transaction, CONTRACT_ID,
+    };
+    use near_sdk::testing_env;
+
+    fn setup_multisig() -> (AbstractAccountContract, Transaction) {
+        let (mut contract, account_id) = setup_account(vec![
+            full_access("bob"),
+            full_access("carol"),
+            IdentityWithPermissions {
+                identity: identity("dave"),
+                permissions: Some(IdentityPermissions {
+                    enable_act_as: false,
+                }),
+            },
+        ]);
+        contract
+            .accounts
+            .get_mut(&account_id)
+            .unwrap()
+            .multisig_policy = Some(MultisigPolicy {
+            threshold: 2,
+            actions: None,
+        });
+
+        let transaction = transaction(&account_id, vec![Action::RemoveAccount]);
+
+        (contract, transaction)
+    }
+
+    #[test]
+    fn approve_proposal_should_execute_at_threshold() {
+        let (mut contract, transaction) = setup_multisig();
+        let predecessor = CONTRACT_ID.parse().unwrap();
+
+        let proposal = contract.approve_proposal(
+            &predecessor,
+            vec![identity("bob")],
+            None,
+            transaction.clone(),
+            false,
+        );
+        assert!(matches!(proposal, Ok(None)));
+        assert!(contract.proposals.contains_key(&transaction.hash()));
+
+        let proposal = contract
+            .approve_proposal(
+                &predecessor,
+                vec![identity("carol")],
+                None,
+                transaction.clone(),
+                true,
+            )
+            .unwrap()
+            .expect("Proposal should be approved");
+        assert_eq!(proposal.approvals, vec![identity("bob"), identity("carol")]);
+        assert!(!contract.proposals.contains_key(&transaction.hash()));
+    }
+
+    #[test]
+    fn approve_proposal_should_require_threshold_to_add_identities_or_guardians() {
+        let (mut contract, remove_account) = setup_multisig();
+        let predecessor = CONTRACT_ID.parse().unwrap();
+        let account_id = remove_account.account_id.clone();
+        contract
+            .accounts
+            .get_mut(&account_id)
+            .unwrap()
+            .multisig_policy = Some(MultisigPolicy {
+            threshold: 2,
+            actions: Some(vec![ActionKind::RemoveIdentity, ActionKind::RemoveAccount]),
+        });
+
+        // A second full access identity controlled by bob would let bob alone reach the threshold
+        for action in [
+            Action::AddIdentity(full_access("eve")),
+            Action::SetRecoveryConfig(None),
+        ] {
+            let transaction = transaction(&account_id, vec![action]);
+            assert_eq!(
+                contract
+                    .accounts
+                    .get(&account_id)
+                    .unwrap()
+                    .required_approvals(&transaction.actions),
+                2
+            );
+
+            let proposal = contract.approve_proposal(
+                &predecessor,
+                vec![identity("bob")],
+                None,
+                transaction.clone(),
+                false,
+            );
+            assert!(matches!(proposal, Ok(None)));
+        }
+    }
+
+    #[test]
+    fn approve_proposal_should_reject_without_recording() {
+        let (mut contract, transaction) = setup_multisig();
+        let predecessor = CONTRACT_ID.parse().unwrap();
+
+        let result = contract.approve_proposal(
+            &predecessor,
+            vec![identity("dave")],
+            None,
+            transaction.clone(),
+            false,
+        );
+        assert_eq!(
+            result.err().as_deref(),
+            Some("Only full access identities can approve proposals")
+        );
+        assert!(!contract.proposals.contains_key(&transaction.hash()));
+
+        contract
+            .approve_proposal(
+                &predecessor,
+                vec![identity("bob")],
+                None,
+                transaction.clone(),
+                false,
+            )
+            .unwrap();
+        let result = contract.approve_proposal(
+            &predecessor,
+            vec![identity("bob")],
+            None,
+            transaction.clone(),
+            true,
+        );
+        assert_eq!(
+            result.err().as_deref(),
+            Some("Identity already approved the proposal")
+        );
+        assert_eq!(
+            contract
+                .proposals
+                .get(&transaction.hash())
+                .unwrap()
+                .approvals,
+            vec![identity("bob")]
+        );
+    }
+
+    #[test]
+    fn expired_proposals_should_be_pruned() {
+        let (mut contract, transaction) = setup_multisig();
+        let predecessor = CONTRACT_ID.parse().unwrap();
+
+        contract
+            .approve_proposal(
+                &predecessor,
+                vec![identity("bob")],
+                None,
+                transaction.clone(),
+                false,
+            )
+            .unwrap();
+        assert_eq!(
+            contract
+                .list_proposals(transaction.account_id.clone())
+                .len(),
+            1
+        );
+
+        testing_env!(context()
+            .block_timestamp(env::block_timestamp() + PROPOSAL_TTL + 1)
+            .build());
+
+        let result = contract.approve_proposal(
+            &predecessor,
+            vec![identity("carol")],
+            None,
+            transaction.clone(),
+            true,
+        );
+        assert_eq!(
+            result.err().as_deref(),
+            Some("Proposal is no longer pending")
+        );
+        assert!(contract
+            .list_proposals(transaction.account_id.clone())
+            .is_empty());
+
+        let next_transaction = Transaction {
+            nonce: 1,
+            ..transaction.clone()
+        };
+        contract
+            .approve_proposal(
+                &predecessor,
+                vec![identity("bob")],
+                None,
+                next_transaction.clone(),
+                false,
+            )
+            .unwrap();
+
+        assert!(!contract.proposals.contains_key(&transaction.hash()));
+        let proposals = contract.list_proposals(transaction.account_id.clone());
+        assert_eq!(proposals.len(), 1);
+        assert_eq!(proposals[0].0, next_transaction.hash());
+    }
+}
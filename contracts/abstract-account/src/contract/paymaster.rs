@@ -0,0 +1,194 @@
+use near_sdk::{assert_one_yocto, env, json_types::U128, require, NearToken, Promise};
+use near_sdk_contract_tools::nft::nep145::{Nep145, Nep145Controller};
+
+use crate::types::paymaster::{Sponsor, SponsorPolicy, SponsoredFee, Sponsorship, GAS_PRICE};
+use crate::*;
+
+#[near]
+impl AbstractAccountContract {
+    /// Adds the attached deposit to the predecessor sponsor balance, registering it as sponsor if needed
+    ///
+    /// # Arguments
+    /// * `policy` - Replaces the sponsor policy when provided, required to register as sponsor
+    #[payable]
+    pub fn sponsor_deposit(&mut self, policy: Option<SponsorPolicy>) -> Sponsor {
+        let storage_usage_start = env::storage_usage();
+        let predecessor = env::predecessor_account_id();
+        self.storage_balance_of(predecessor.clone())
+            .expect("Predecessor has not registered for storage");
+
+        let sponsor =
+            self.sponsors.entry(predecessor.clone()).or_insert_with(|| {
+                // Sponsors must choose the accounts they pay for and their daily cap
+                Sponsor::new(policy.clone().unwrap_or_else(|| {
+                    env::panic_str("A policy is required to register as sponsor")
+                }))
+            });
+
+        sponsor.balance = U128(sponsor.balance.0 + env::attached_deposit().as_yoctonear());
+        if let Some(policy) = policy {
+            sponsor.policy = policy;
+        }
+
+        let sponsor = sponsor.clone();
+        self.sponsors.flush();
+
+        self.storage_accounting(&predecessor, storage_usage_start)
+            .expect("Storage accounting failed");
+
+        sponsor
+    }
+
+    pub fn set_sponsor_policy(&mut self, policy: SponsorPolicy) {
+        let storage_usage_start = env::storage_usage();
+        let predecessor = env::predecessor_account_id();
+
+        self.sponsors
+            .get_mut(&predecessor)
+            .expect("Sponsor not found")
+            .policy = policy;
+        self.sponsors.flush();
+
+        self.storage_accounting(&predecessor, storage_usage_start)
+            .expect("Storage accounting failed");
+    }
+
+    /// Withdraws from the predecessor sponsor balance, the whole balance if no amount is provided.
+    /// Requires 1 yoctoNEAR attached, so it's signed with a full access key
+    #[payable]
+    pub fn sponsor_withdraw(&mut self, amount: Option<U128>) -> Promise {
+        assert_one_yocto();
+        let predecessor = env::predecessor_account_id();
+        let sponsor = self
+            .sponsors
+            .get_mut(&predecessor)
+            .expect("Sponsor not found");
+
+        let amount = amount.map_or(sponsor.balance.0, |amount| amount.0);
+        require!(amount <= sponsor.balance.0, "Insufficient sponsor balance");
+
+        sponsor.balance = U128(sponsor.balance.0 - amount);
+
+        Promise::new(predecessor).transfer(NearToken::from_yoctonear(amount))
+    }
+
+    pub fn get_sponsor(&self, sponsor_id: AccountId) -> Option<&Sponsor> {
+        self.sponsors.get(&sponsor_id)
+    }
+
+    /// Submits a user operation paid by a sponsor. The predecessor relayer is reimbursed the fixed gas cost of the
+    /// operation actions set by the sponsor policy once the operation is authenticated, and the signature fees
+    /// of the operation are paid from the sponsor balance up to max_fee
+    ///
+    /// # Arguments
+    /// * `user_op` - The user operation, which must be allowed by the sponsor policy
+    /// * `sponsor_id` - The sponsor paying for the operation
+    /// * `max_fee` - Signature fees the sponsor pays at most, the rest is paid from the account balance.
+    ///   They're only spent on the operation Sign actions, never credited to the account, and capped by the policy max_fee_per_op
+    pub fn auth_sponsored(
+        &mut self,
+        user_op: UserOp,
+        sponsor_id: AccountId,
+        max_fee: Option<U128>,
+    ) -> Promise {
+        let sponsor = self
+            .sponsors
+            .get_mut(&sponsor_id)
+            .expect("Sponsor not found");
+
+        require!(
+            sponsor.policy.allows(&user_op.transaction),
+            "User operation not allowed by the sponsor policy"
+        );
+
+        let gas_fee = sponsor
+            .policy
+            .reimbursed_gas(&user_op.transaction, env::prepaid_gas().as_gas())
+            as u128
+            * GAS_PRICE;
+
+        sponsor.charge(gas_fee);
+
+        let sponsorship = Sponsorship {
+            sponsor_id,
+            relayer: env::predecessor_account_id(),
+            day: sponsor.day,
+            gas_fee: U128(gas_fee),
+            max_fee: sponsor.policy.max_fee(max_fee),
+        };
+
+        // The sponsor pays for the storage used by the operation
+        self.authenticate(
+            user_op,
+            sponsorship.sponsor_id.clone(),
+            NearToken::from_yoctonear(0),
+            Some(sponsorship),
+        )
+    }
+}
+
+impl AbstractAccountContract {
+    /// Pays the relayer of an authenticated sponsored user operation
+    pub fn reimburse_relayer(&self, sponsorship: &Sponsorship) {
+        if sponsorship.gas_fee.0 > 0 {
+            Promise::new(sponsorship.relayer.clone())
+                .transfer(NearToken::from_yoctonear(sponsorship.gas_fee.0));
+        }
+    }
+
+    /// Credits the sponsor back the cost of a user operation that failed authentication
+    pub fn refund_sponsor(&mut self, sponsorship: &Sponsorship) {
+        if let Some(sponsor) = self.sponsors.get_mut(&sponsorship.sponsor_id) {
+            sponsor.refund(sponsorship.gas_fee.0, sponsorship.day);
+        }
+    }
+
+    /// Charges the signature fees of a Sign action of a sponsored user operation to its sponsor,
+    /// up to what the previous Sign actions left of the sponsorship max_fee
+    ///
+    /// # Arguments
+    /// * `sponsored_fee` - The fees charged to the sponsor for the operation so far, increased by the charged amount
+    ///
+    /// # Returns
+    /// * The fees charged to the sponsor, the rest is paid by the account
+    pub fn charge_sponsored_fee(
+        &mut self,
+        sponsorship: &Sponsorship,
+        sponsored_fee: &mut Option<SponsoredFee>,
+        fee: u128,
+    ) -> u128 {
+        let charged = sponsored_fee.as_ref().map_or(0, |fee| fee.amount.0);
+        let amount = fee.min(sponsorship.max_fee.0.saturating_sub(charged));
+        if amount == 0 {
+            return 0;
+        }
+
+        let sponsor = self
+            .sponsors
+            .get_mut(&sponsorship.sponsor_id)
+            .expect("Sponsor not found");
+        sponsor.charge(amount);
+
+        let sponsored_fee = sponsored_fee.get_or_insert_with(|| SponsoredFee {
+            sponsor_id: sponsorship.sponsor_id.clone(),
+            day: sponsor.day,
+            amount: U128(0),
+        });
+        sponsored_fee.amount = U128(sponsored_fee.amount.0 + amount);
+
+        amount
+    }
+
+    /// Refunds the signature fees of failed sign requests, first to the sponsor up to the fees it paid
+    ///
+    /// # Returns
+    /// * The part of the refund that goes back to the account
+    pub fn refund_sponsored_fee(&mut self, sponsored_fee: &SponsoredFee, refund: u128) -> u128 {
+        let sponsor_refund = refund.min(sponsored_fee.amount.0);
+        if let Some(sponsor) = self.sponsors.get_mut(&sponsored_fee.sponsor_id) {
+            sponsor.refund(sponsor_refund, sponsored_fee.day);
+        }
+
+        refund - sponsor_refund
+    }
+}
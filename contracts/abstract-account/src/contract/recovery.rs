@@ -0,0 +1,396 @@
+use near_sdk::{env, json_types::U64, require, Promise};
+use near_sdk_contract_tools::nft::nep145::{Nep145, Nep145Controller};
+
+use crate::types::recovery::{
+    PendingRecovery, RecoveryApproval, RecoveryConfig, MAX_RECOVERY_DELAY,
+};
+use crate::types::transaction::Auth;
+use crate::*;
+use std::collections::BTreeMap;
+
+#[near]
+impl AbstractAccountContract {
+    /// Approves a recovery as a guardian authenticated by its credentials
+    ///
+    /// # Arguments
+    /// * `approval` - The recovery being approved, signed by the guardian
+    /// * `guardian` - The guardian identity and its credentials over the approval message
+    pub fn approve_recovery(&mut self, approval: RecoveryApproval, guardian: Auth) -> Promise {
+        let account = self
+            .accounts
+            .get(&approval.account_id)
+            .expect("Account not found");
+        let config = account
+            .recovery_config
+            .as_ref()
+            .expect("Recovery not configured for the account");
+
+        // The stored guardian carries the full identity data, e.g. the WebAuthn compressed public key
+        let guardian_identity = config
+            .guardians
+            .iter()
+            .find(|curr| **curr == guardian.identity)
+            .cloned()
+            .expect("Identity is not a guardian of the account");
+
+        require!(
+            !matches!(guardian_identity, Identity::Account(_)),
+            "Account guardians must approve through Action::ApproveRecovery"
+        );
+        require!(approval.epoch == account.epoch, "Account epoch mismatch");
+        require!(
+            approval.nonce == account.recovery_nonce,
+            "Recovery nonce mismatch"
+        );
+
+        let encoding = guardian
+            .encoding
+            .unwrap_or_else(|| guardian_identity.default_message_encoding());
+        let signed_message = approval.encode_message(&self.domain(encoding), encoding);
+
+        self.validate_credentials(
+            guardian_identity.clone(),
+            guardian.credentials,
+            signed_message,
+            Self::ext(env::current_account_id()).approve_recovery_callback(
+                approval,
+                guardian_identity,
+                env::predecessor_account_id(),
+            ),
+        )
+    }
+
+    #[private]
+    pub fn approve_recovery_callback(
+        &mut self,
+        approval: RecoveryApproval,
+        guardian: Identity,
+        predecessor: AccountId,
+        #[callback_result] auth_result: Result<bool, near_sdk::PromiseError>,
+    ) {
+        match auth_result {
+            Ok(true) => {
+                let storage_usage_start = env::storage_usage();
+                self.storage_balance_of(predecessor.clone())
+                    .expect("Predecessor has not registered for storage");
+
+                self.record_recovery_approval(guardian, approval);
+                self.accounts.flush();
+
+                self.storage_accounting(&predecessor, storage_usage_start)
+                    .expect("Storage accounting failed");
+            }
+            Ok(false) => env::panic_str("Authentication failed"),
+            Err(_) => env::panic_str("Error validating authentication"),
+        }
+    }
+
+    /// Adds the recovered identity with full access once the recovery delay has elapsed, dropping the other pending recoveries.
+    /// When several identities reached the guardians threshold, the one that reached it first is recovered. Callable by anyone.
+    pub fn finalize_recovery(&mut self, account_id: AbstractAccountId) {
+        let storage_usage_start = env::storage_usage();
+        let predecessor = env::predecessor_account_id();
+        self.storage_balance_of(predecessor.clone())
+            .expect("Predecessor has not registered for storage");
+
+        let account = self
+            .accounts
+            .get_mut(&account_id)
+            .expect("Account not found");
+        require!(
+            !account.pending_recoveries.is_empty(),
+            "No pending recovery"
+        );
+        let executable_at = account
+            .pending_recoveries
+            .iter()
+            .filter_map(|pending| pending.executable_at)
+            .min()
+            .expect("Recovery has not reached the guardians threshold");
+
+        require!(
+            env::block_timestamp() >= executable_at.0,
+            "Recovery delay has not elapsed"
+        );
+
+        let pending = account
+            .pending_recoveries
+            .drain(..)
+            .find(|pending| pending.executable_at == Some(executable_at))
+            .unwrap();
+
+        // An existing identity is upgraded to full access instead of being duplicated
+        account.remove_identity(&pending.identity);
+        account.add_identity(IdentityWithPermissions {
+            identity: pending.identity.clone(),
+            permissions: None,
+        });
+        account.recovery_nonce += 1;
+
+        self.index_identity(&pending.identity, &account_id);
+
+        self.accounts.flush();
+
+        self.storage_accounting(&predecessor, storage_usage_start)
+            .expect("Storage accounting failed");
+    }
+
+    pub fn get_pending_recoveries(&self, account_id: AbstractAccountId) -> Vec<PendingRecovery> {
+        self.accounts
+            .get(&account_id)
+            .map_or(vec![], |account| account.pending_recoveries.clone())
+    }
+}
+
+impl AbstractAccountContract {
+    /// Current epochs of the Identity::Account guardians, which must be existing accounts
+    pub fn guardian_epochs(&self, config: &RecoveryConfig) -> BTreeMap<AbstractAccountId, u64> {
+        config
+            .guardians
+            .iter()
+            .filter_map(|guardian| match guardian {
+                Identity::Account(guardian_account_id) => Some(guardian_account_id),
+                _ => None,
+            })
+            .map(|guardian_account_id| {
+                let account = self
+                    .accounts
+                    .get(guardian_account_id)
+                    .expect("Guardian account not found");
+
+                (guardian_account_id.clone(), account.epoch)
+            })
+            .collect()
+    }
+
+    /// Records a guardian approval, starting the recovery delay once the guardians threshold is reached
+    pub fn record_recovery_approval(&mut self, guardian: Identity, approval: RecoveryApproval) {
+        let config = self
+            .accounts
+            .get(&approval.account_id)
+            .expect("Account not found")
+            .recovery_config
+            .clone()
+            .expect("Recovery not configured for the account");
+
+        require!(
+            config.guardians.contains(&guardian),
+            "Identity is not a guardian of the account"
+        );
+
+        if let Identity::Account(ref guardian_account_id) = guardian {
+            let epoch = self
+                .accounts
+                .get(guardian_account_id)
+                .map(|account| account.epoch);
+            require!(
+                epoch.is_some()
+                    && config.guardian_epochs.get(guardian_account_id) == epoch.as_ref(),
+                "Guardian account was recreated since the recovery config was set"
+            );
+        }
+
+        let account = self.accounts.get_mut(&approval.account_id).unwrap();
+        require!(approval.epoch == account.epoch, "Account epoch mismatch");
+        require!(
+            approval.nonce == account.recovery_nonce,
+            "Recovery nonce mismatch"
+        );
+
+        if let Identity::WebAuthn(ref webauthn) = approval.identity {
+            require!(
+                webauthn.compressed_public_key.is_some(),
+                "WebAuthn identity must include the compressed public key"
+            );
+        }
+
+        // A guardian backs a single identity at a time, approving another identity moves its approval
+        for pending in account.pending_recoveries.iter_mut() {
+            if pending.identity != approval.identity {
+                pending.approvals.retain(|curr| *curr != guardian);
+
+                if (pending.approvals.len() as u32) < config.threshold {
+                    pending.executable_at = None;
+                }
+            }
+        }
+        account
+            .pending_recoveries
+            .retain(|pending| !pending.approvals.is_empty());
+
+        let index = match account
+            .pending_recoveries
+            .iter()
+            .position(|pending| pending.identity == approval.identity)
+        {
+            Some(index) => index,
+            None => {
+                account.pending_recoveries.push(PendingRecovery {
+                    identity: approval.identity.clone(),
+                    approvals: vec![],
+                    executable_at: None,
+                });
+                account.pending_recoveries.len() - 1
+            }
+        };
+        let pending = &mut account.pending_recoveries[index];
+
+        require!(
+            !pending.approvals.contains(&guardian),
+            "Guardian already approved the recovery"
+        );
+
+        pending.approvals.push(guardian);
+
+        if pending.executable_at.is_none() && pending.approvals.len() as u32 >= config.threshold {
+            let executable_at = env::block_timestamp().checked_add(config.delay.0);
+            require!(executable_at.is_some(), "Recovery delay overflow");
+
+            pending.executable_at = executable_at.map(U64);
+        }
+    }
+}
+
+pub fn validate_recovery_config(account_id: &AbstractAccountId, config: &RecoveryConfig) {
+    require!(
+        config.threshold >= 1 && config.threshold as usize <= config.guardians.len(),
+        "Recovery threshold must be between 1 and the number of guardians"
+    );
+    require!(
+        config.delay.0 <= MAX_RECOVERY_DELAY,
+        "Recovery delay can't exceed 30 days"
+    );
+
+    for (index, guardian) in config.guardians.iter().enumerate() {
+        require!(
+            !config.guardians[..index].contains(guardian),
+            "Duplicated guardian"
+        );
+
+        match guardian {
+            Identity::Account(guardian_account_id) => require!(
+                guardian_account_id != account_id,
+                "An account can't be its own guardian"
+            ),
+            Identity::WebAuthn(webauthn) => require!(
+                webauthn.compressed_public_key.is_some(),
+                "WebAuthn guardians must include the compressed public key"
+            ),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::{full_access, identity, setup_account, CONTRACT_ID};
+
+    fn setup_recovery() -> (AbstractAccountContract, AbstractAccountId) {
+        let (mut contract, account_id) = setup_account(vec![full_access("bob")]);
+        for guardian in ["g1", "g2", "g3"] {
+            contract.add_account(guardian.parse().unwrap(), full_access("eve"), None);
+        }
+
+        contract.handle_account_action(
+            CONTRACT_ID.parse().unwrap(),
+            account_id.clone(),
+            Action::SetRecoveryConfig(Some(RecoveryConfig {
+                guardians: vec![identity("g1"), identity("g2"), identity("g3")],
+                threshold: 2,
+                delay: U64(0),
+                guardian_epochs: BTreeMap::new(),
+            })),
+        );
+
+        (contract, account_id)
+    }
+
+    fn approval(account_id: &AbstractAccountId, name: &str) -> RecoveryApproval {
+        RecoveryApproval {
+            account_id: account_id.clone(),
+            epoch: 1,
+            identity: identity(name),
+            nonce: 1,
+        }
+    }
+
+    #[test]
+    fn record_recovery_approval_should_not_let_a_guardian_block_other_identities() {
+        let (mut contract, account_id) = setup_recovery();
+
+        contract.record_recovery_approval(identity("g1"), approval(&account_id, "rogue"));
+        contract.record_recovery_approval(identity("g2"), approval(&account_id, "carol"));
+        contract.record_recovery_approval(identity("g3"), approval(&account_id, "carol"));
+
+        let pending_recoveries = contract.get_pending_recoveries(account_id.clone());
+        assert_eq!(pending_recoveries.len(), 2);
+        assert!(pending_recoveries[0].executable_at.is_none());
+        assert!(pending_recoveries[1].executable_at.is_some());
+
+        contract.finalize_recovery(account_id.clone());
+
+        let account = contract.accounts.get(&account_id).unwrap();
+        assert!(account.has_full_access(&identity("carol")));
+        assert!(account.get_identity(&identity("rogue")).is_none());
+        assert!(account.pending_recoveries.is_empty());
+        assert_eq!(account.recovery_nonce, 2);
+    }
+
+    #[test]
+    fn record_recovery_approval_should_move_the_guardian_approval() {
+        let (mut contract, account_id) = setup_recovery();
+
+        contract.record_recovery_approval(identity("g1"), approval(&account_id, "carol"));
+        contract.record_recovery_approval(identity("g2"), approval(&account_id, "carol"));
+        assert!(contract.get_pending_recoveries(account_id.clone())[0]
+            .executable_at
+            .is_some());
+
+        contract.record_recovery_approval(identity("g2"), approval(&account_id, "dave"));
+
+        let pending_recoveries = contract.get_pending_recoveries(account_id.clone());
+        assert_eq!(pending_recoveries.len(), 2);
+        assert_eq!(pending_recoveries[0].approvals, vec![identity("g1")]);
+        assert!(pending_recoveries[0].executable_at.is_none());
+        assert_eq!(pending_recoveries[1].approvals, vec![identity("g2")]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Recovery has not reached the guardians threshold")]
+    fn finalize_recovery_should_require_the_guardians_threshold() {
+        let (mut contract, account_id) = setup_recovery();
+
+        contract.record_recovery_approval(identity("g1"), approval(&account_id, "carol"));
+
+        contract.finalize_recovery(account_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Guardian account was recreated since the recovery config was set")]
+    fn record_recovery_approval_should_reject_a_recreated_guardian_account() {
+        let (mut contract, account_id) = setup_recovery();
+
+        contract.remove_account("g1".parse().unwrap());
+        contract.add_account("g1".parse().unwrap(), full_access("mallory"), None);
+
+        contract.record_recovery_approval(identity("g1"), approval(&account_id, "mallory"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Guardian account not found")]
+    fn set_recovery_config_should_require_existing_guardian_accounts() {
+        let (mut contract, account_id) = setup_account(vec![full_access("bob")]);
+
+        contract.handle_account_action(
+            CONTRACT_ID.parse().unwrap(),
+            account_id,
+            Action::SetRecoveryConfig(Some(RecoveryConfig {
+                guardians: vec![identity("g1")],
+                threshold: 1,
+                delay: U64(0),
+                guardian_epochs: BTreeMap::new(),
+            })),
+        );
+    }
+}
@@ -1,37 +1,309 @@
 use crate::*;
 use crate::{types::identity::Identity, AbstractAccountContract};
-use interfaces::traits::path::Path;
-use mods::signer::{ext_signer, SignRequest, SIGN_GAS};
-use near_sdk::{env, near, Promise};
-use types::transaction::SignPayloadsRequest;
+use mods::signer::{
+    ext_signer, SignRequestArgs, SignResponse, SignResult, SignStatus, DERIVED_PUBLIC_KEY_GAS,
+    SIGNATURE_DEPOSIT_GAS, SIGN_GAS,
+};
+use near_sdk::{
+    env, json_types::U128, near, require, Promise, PromiseError, PromiseResult, PublicKey,
+};
+use types::address::{AddressChain, DerivedAddress};
+use types::paymaster::SponsoredFee;
+use types::signer::SignCall;
 use utils::utils::build_account_path;
 
 #[near]
 impl AbstractAccountContract {
+    /// Requests all the signatures at once, each with the deposit quoted by its signer. Each signer is quoted again
+    /// right after its requests, so sign_callback can refund the surplus the signer returns when its fee dropped
+    ///
+    /// # Arguments
+    /// * `account_id` - The account paying the signature fees, refunded for the failed requests
+    /// * `sign_calls` - The payloads to sign with their signer, signing root and deposit
+    /// * `sponsored_fee` - The part of the fees paid by a sponsor, refunded to it first
     #[private]
-    pub fn sign(&self, identity: Identity, sign_payloads_request: SignPayloadsRequest) -> Promise {
-        let mut promise = Promise::new(
-            sign_payloads_request
-                .contract_id
-                .parse()
-                .expect("Invalid contract_id"),
-        );
-        let deposit_per_call =
-            env::attached_deposit().saturating_div(sign_payloads_request.payloads.len() as u128);
+    pub fn sign(
+        &self,
+        account_id: AbstractAccountId,
+        sign_calls: Vec<SignCall>,
+        sponsored_fee: Option<SponsoredFee>,
+    ) -> Promise {
+        let mut signers: Vec<AccountId> = vec![];
+        for call in &sign_calls {
+            if !signers.contains(&call.signer_id) {
+                signers.push(call.signer_id.clone());
+            }
+        }
 
-        for payload in sign_payloads_request.payloads {
-            let path = build_account_path(identity.path(), payload.path);
+        // The quotes are joined after the requests, so they're received by the signers after them
+        let quotes = signers.iter().map(|signer_id| {
+            ext_signer::ext(signer_id.clone())
+                .with_static_gas(SIGNATURE_DEPOSIT_GAS)
+                .experimental_signature_deposit()
+        });
 
-            let sign_request = SignRequest::new(payload.payload, path, payload.key_version);
+        sign_calls
+            .iter()
+            .map(|call| {
+                let path = build_account_path(call.signing_root.clone(), call.request.path.clone());
 
-            promise = promise.then(
-                ext_signer::ext(self.signer_account.clone())
-                    .with_attached_deposit(deposit_per_call)
+                let sign_request = SignRequestArgs::new(
+                    call.version,
+                    call.scheme,
+                    &call.request.payload,
+                    path,
+                    call.request.key_version,
+                );
+
+                ext_signer::ext(call.signer_id.clone())
+                    .with_attached_deposit(call.deposit)
                     .with_static_gas(SIGN_GAS)
-                    .sign(sign_request),
+                    .sign(sign_request)
+            })
+            .chain(quotes)
+            .reduce(|joined, promise| joined.and(promise))
+            .unwrap_or_else(|| env::panic_str("At least one payload is required"))
+            .then(Self::ext(env::current_account_id()).sign_callback(
+                account_id,
+                sign_calls,
+                signers,
+                sponsored_fee,
+            ))
+    }
+
+    /// Gathers the signature of every payload and refunds the deposit of the failed requests and the surplus of the
+    /// successful ones, to the sponsor up to the fees it paid and then to the account balance, never to the caller
+    ///
+    /// # Arguments
+    /// * `sign_calls` - The signed payloads, in the order their requests were joined
+    /// * `signers` - The signers quoted after the requests, in the order their quotes were joined
+    /// * `sponsored_fee` - The part of the fees paid by a sponsor
+    ///
+    /// # Returns
+    /// The outcome of each payload, in the order of the Sign actions
+    #[private]
+    pub fn sign_callback(
+        &mut self,
+        account_id: AbstractAccountId,
+        sign_calls: Vec<SignCall>,
+        signers: Vec<AccountId>,
+        sponsored_fee: Option<SponsoredFee>,
+    ) -> Vec<SignResponse> {
+        /*
+        A signer charging less than the deposit returns the surplus to this contract. The fee quoted right after
+        the requests counts them as pending, so it's never lower than the fee they were charged and the surplus
+        isn't overestimated. Without a quote, no surplus is refunded.
+        */
+        let fees: Vec<Option<u128>> = (0..signers.len())
+            .map(
+                |position| match env::promise_result((sign_calls.len() + position) as u64) {
+                    PromiseResult::Successful(value) => {
+                        serde_json::from_slice::<U128>(&value).ok().map(|fee| fee.0)
+                    }
+                    PromiseResult::Failed => None,
+                },
             )
+            .collect();
+
+        // Failed requests return their whole deposit, an invalid result was still charged by the signer
+        let mut refund = 0;
+
+        let responses: Vec<SignResponse> = sign_calls
+            .into_iter()
+            .enumerate()
+            .map(|(index, call)| {
+                let status = match env::promise_result(index as u64) {
+                    PromiseResult::Successful(value) => {
+                        let position = signers
+                            .iter()
+                            .position(|signer_id| *signer_id == call.signer_id)
+                            .unwrap();
+                        if let Some(fee) = fees[position] {
+                            refund += call.deposit.as_yoctonear().saturating_sub(fee);
+                        }
+
+                        SignResult::from_slice(&value).map_or_else(
+                            || SignStatus::Failed("Invalid signature result".to_string()),
+                            SignStatus::Signed,
+                        )
+                    }
+                    PromiseResult::Failed => {
+                        refund += call.deposit.as_yoctonear();
+                        SignStatus::Failed("Signature request failed".to_string())
+                    }
+                };
+
+                SignResponse {
+                    signer_id: call.signer_id,
+                    scheme: call.scheme,
+                    payload: call.request.payload,
+                    path: call.request.path,
+                    key_version: call.request.key_version,
+                    status,
+                }
+            })
+            .collect();
+
+        // Failed requests and surpluses return to this contract, so they're credited back to whoever paid them
+        if let Some(sponsored_fee) = &sponsored_fee {
+            refund = self.refund_sponsored_fee(sponsored_fee, refund);
+        }
+
+        if let Some(account) = self.accounts.get_mut(&account_id) {
+            account.balance = U128(account.balance.0 + refund);
+        }
+
+        responses
+    }
+
+    /// Path the signer derives the key from, as used by the Sign action
+    ///
+    /// # Arguments
+    /// * `account_id` - The account signing the payloads
+    /// * `identity` - The act_as identity, only required in Identity signing path mode
+    /// * `path` - The path of the Sign payloads
+    pub fn get_derivation_path(
+        &self,
+        account_id: AbstractAccountId,
+        identity: Option<Identity>,
+        path: String,
+    ) -> String {
+        self.derivation_path(&account_id, identity.as_ref(), path)
+    }
+
+    /// Fetches the key derived by the signer and formats it as an address of the given chain.
+    /// It's a change call, not a view: views can't make the cross-contract call to the signer.
+    /// get_derivation_path returns the path in a view, for deriving the key off-chain
+    ///
+    /// # Arguments
+    /// * `account_id` - The account signing the payloads
+    /// * `identity` - The act_as identity, only required in Identity signing path mode
+    /// * `path` - The path of the Sign payloads
+    /// * `chain` - The chain whose address format is returned
+    /// * `signer_id` - The registered signer deriving the key, the default signer when omitted
+    pub fn derive_address(
+        &mut self,
+        account_id: AbstractAccountId,
+        identity: Option<Identity>,
+        path: String,
+        chain: AddressChain,
+        signer_id: Option<String>,
+    ) -> Promise {
+        let path = self.derivation_path(&account_id, identity.as_ref(), path);
+
+        let (signer_id, config) = self.resolve_signer(signer_id.as_ref());
+        require!(
+            config.schemes.contains(&chain.scheme()),
+            "Scheme not supported by the signer"
+        );
+
+        ext_signer::ext(signer_id)
+            .with_static_gas(DERIVED_PUBLIC_KEY_GAS)
+            .derived_public_key(
+                path.clone(),
+                Some(env::current_account_id()),
+                Some(chain.scheme().domain_id()),
+            )
+            .then(Self::ext(env::current_account_id()).derive_address_callback(path, chain))
+    }
+
+    #[private]
+    pub fn derive_address_callback(
+        &self,
+        #[callback_result] public_key: Result<PublicKey, PromiseError>,
+        path: String,
+        chain: AddressChain,
+    ) -> DerivedAddress {
+        let public_key = public_key.expect("Failed to fetch the derived public key");
+
+        DerivedAddress {
+            address: chain.format_address(&public_key, &self.network_id),
+            public_key: String::from(&public_key),
+            path,
         }
+    }
+}
+
+impl AbstractAccountContract {
+    fn derivation_path(
+        &self,
+        account_id: &AbstractAccountId,
+        identity: Option<&Identity>,
+        path: String,
+    ) -> String {
+        let account = self.accounts.get(account_id).expect("Account not found");
+
+        build_account_path(account.signing_root(account_id, identity), path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mods::signer::{SignRequest, SignatureScheme};
+    use crate::types::signer::SignerVersion;
+    use crate::utils::test_utils::{full_access, set_promise_results, setup_account, SIGNER_ID};
+
+    const DEPOSIT: u128 = 10;
+
+    fn sign_call() -> SignCall {
+        SignCall {
+            signer_id: SIGNER_ID.parse().unwrap(),
+            version: SignerVersion::V2,
+            scheme: SignatureScheme::Eddsa,
+            signing_root: "alice".to_string(),
+            request: SignRequest {
+                payload: vec![0; 32],
+                path: "solana,1".to_string(),
+                key_version: 0,
+            },
+            deposit: NearToken::from_yoctonear(DEPOSIT),
+        }
+    }
+
+    fn signed() -> PromiseResult {
+        PromiseResult::Successful(br#"{"scheme":"Ed25519","signature":[1,2,3]}"#.to_vec())
+    }
+
+    fn quote(fee: u128) -> PromiseResult {
+        PromiseResult::Successful(serde_json::to_vec(&U128(fee)).unwrap())
+    }
+
+    #[test]
+    fn sign_callback_should_refund_the_failed_deposits_and_the_signer_surplus() {
+        let (mut contract, account_id) = setup_account(vec![full_access("bob")]);
+
+        // The signer fee dropped to 4 after the first request was signed
+        set_promise_results(vec![signed(), PromiseResult::Failed, quote(4)]);
+
+        let responses = contract.sign_callback(
+            account_id.clone(),
+            vec![sign_call(), sign_call()],
+            vec![SIGNER_ID.parse().unwrap()],
+            None,
+        );
+
+        assert!(matches!(responses[0].status, SignStatus::Signed(_)));
+        assert!(matches!(responses[1].status, SignStatus::Failed(_)));
+        assert_eq!(
+            contract.accounts.get(&account_id).unwrap().balance.0,
+            DEPOSIT - 4 + DEPOSIT
+        );
+    }
+
+    #[test]
+    fn sign_callback_should_not_refund_a_surplus_without_a_quote() {
+        let (mut contract, account_id) = setup_account(vec![full_access("bob")]);
+
+        set_promise_results(vec![signed(), PromiseResult::Failed]);
+
+        contract.sign_callback(
+            account_id.clone(),
+            vec![sign_call()],
+            vec![SIGNER_ID.parse().unwrap()],
+            None,
+        );
 
-        promise
+        assert_eq!(contract.accounts.get(&account_id).unwrap().balance.0, 0);
     }
 }
@@ -0,0 +1,137 @@
+use crate::mods::signer::{ext_signer, LATEST_KEY_VERSION_GAS, SIGNATURE_DEPOSIT_GAS};
+use crate::types::signer::{SignerConfig, SignerQuote};
+use crate::*;
+use near_sdk::{require, AccountId, Promise};
+use utils::utils::{promise_result_as_u32, promise_result_as_yocto};
+
+#[near]
+impl AbstractAccountContract {
+    /// Registers a signer contract, or replaces the config of a registered one
+    #[private]
+    pub fn set_signer(&mut self, signer_id: AccountId, config: SignerConfig) {
+        require!(
+            !config.schemes.is_empty(),
+            "Signer must support at least one scheme"
+        );
+
+        self.signers.insert(signer_id, config);
+    }
+
+    #[private]
+    pub fn remove_signer(&mut self, signer_id: AccountId) {
+        require!(
+            signer_id != self.signer_account,
+            "Switch the default signer before removing it"
+        );
+
+        self.signers.remove(&signer_id).expect("Signer not found");
+    }
+
+    /// Switches the signer of the Sign actions that don't choose one, e.g. to fail over to another MPC network
+    #[private]
+    pub fn set_default_signer(&mut self, signer_id: AccountId) {
+        require!(self.signers.contains_key(&signer_id), "Signer not found");
+
+        self.signer_account = signer_id;
+    }
+
+    pub fn get_signer(&self, signer_id: AccountId) -> Option<&SignerConfig> {
+        self.signers.get(&signer_id)
+    }
+
+    pub fn list_signers(&self) -> Vec<(AccountId, SignerConfig)> {
+        self.signers
+            .iter()
+            .map(|(signer_id, config)| (signer_id.clone(), config.clone()))
+            .collect()
+    }
+}
+
+impl AbstractAccountContract {
+    /// Resolves a registered signer, the default one when none is chosen
+    pub fn resolve_signer(&self, contract_id: Option<&String>) -> (AccountId, &SignerConfig) {
+        let signer_id = contract_id.map_or_else(
+            || self.signer_account.clone(),
+            |contract_id| contract_id.parse().expect("Invalid signer account"),
+        );
+        let config = self.signers.get(&signer_id).expect("Signer not registered");
+
+        (signer_id, config)
+    }
+
+    /// Validates every Sign action against the signer it chooses
+    ///
+    /// # Panics
+    /// * If the signer is not registered or doesn't support the scheme of the request
+    /// * If a payload is not signable by the scheme of its request
+    pub fn validate_sign_requests(&self, transaction: &Transaction) {
+        for action in &transaction.actions {
+            if let Action::Sign(request) = action {
+                let (_, config) = self.resolve_signer(request.contract_id.as_ref());
+                require!(
+                    config.schemes.contains(&request.scheme()),
+                    "Scheme not supported by the signer"
+                );
+
+                for payload in &request.payloads {
+                    request.scheme().validate_payload(&payload.payload);
+                }
+            }
+        }
+    }
+
+    /// Sets the signer of every Sign action, so switching the default signer doesn't affect transactions being dispatched
+    ///
+    /// # Returns
+    /// The signers of the transaction, without duplicates
+    pub fn pin_signers(&self, transaction: &mut Transaction) -> Vec<AccountId> {
+        let mut signers: Vec<AccountId> = vec![];
+
+        for action in transaction.actions.iter_mut() {
+            if let Action::Sign(request) = action {
+                let (signer_id, _) = self.resolve_signer(request.contract_id.as_ref());
+                request.contract_id = Some(signer_id.to_string());
+
+                if !signers.contains(&signer_id) {
+                    signers.push(signer_id);
+                }
+            }
+        }
+
+        signers
+    }
+
+    /// Fetches the signature fee and the latest key version of each signer, read back with read_signer_quotes
+    pub fn quote_signers(&self, signers: &[AccountId]) -> Option<Promise> {
+        signers
+            .iter()
+            .map(|signer_id| {
+                ext_signer::ext(signer_id.clone())
+                    .with_static_gas(SIGNATURE_DEPOSIT_GAS)
+                    .experimental_signature_deposit()
+                    .and(
+                        ext_signer::ext(signer_id.clone())
+                            .with_static_gas(LATEST_KEY_VERSION_GAS)
+                            .latest_key_version(),
+                    )
+            })
+            .reduce(|joined, promise| joined.and(promise))
+    }
+
+    /// Reads the quotes of quote_signers, joined from the promise result at the given index
+    pub fn read_signer_quotes(signers: Vec<AccountId>, first_index: u64) -> Vec<SignerQuote> {
+        signers
+            .into_iter()
+            .enumerate()
+            .map(|(position, signer_id)| {
+                let index = first_index + 2 * position as u64;
+
+                SignerQuote {
+                    signer_id,
+                    deposit: promise_result_as_yocto(index),
+                    latest_key_version: promise_result_as_u32(index + 1),
+                }
+            })
+            .collect()
+    }
+}
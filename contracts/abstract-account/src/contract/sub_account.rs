@@ -0,0 +1,231 @@
+use crate::mods::proxy::{
+    ext_proxy, PROXY_GAS, PROXY_INIT_GAS, SUB_ACCOUNT_CALLBACK_GAS, SUB_ACCOUNT_DELETED_GAS,
+};
+use crate::types::custody::CustodyAsset;
+use crate::*;
+use near_sdk::{
+    env,
+    json_types::{Base64VecU8, U64},
+    require, AccountId, Gas, NearToken, Promise, PromiseResult,
+};
+
+#[near]
+impl AbstractAccountContract {
+    /// Sets the wasm of the proxy contract deployed by CreateSubAccount
+    #[private]
+    pub fn set_proxy_code(&mut self, code: Base64VecU8) {
+        self.proxy_code.set(Some(code.into()));
+    }
+
+    /// Releases the sub-account of the account if it couldn't be created, refunding its initial balance
+    #[private]
+    pub fn create_sub_account_callback(
+        &mut self,
+        account_id: AbstractAccountId,
+        initial_balance: U128,
+    ) {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return;
+        }
+
+        if let Some(account) = self.accounts.get_mut(&account_id) {
+            account.sub_account = None;
+            account.credit(CustodyAsset::Near(initial_balance));
+        }
+    }
+
+    /// Called by a proxy once its deletion completed, crediting the balance it sent to this contract to its account.
+    /// The proxy calls it as a callback of the deletion, so a direct call, which has no promise result, is rejected
+    pub fn sub_account_deleted(&mut self, balance: U128) {
+        require!(
+            env::promise_results_count() == 1,
+            "Only a deleted proxy can report its balance"
+        );
+
+        let sub_account = env::predecessor_account_id();
+        let account_id: AbstractAccountId = sub_account
+            .as_str()
+            .strip_suffix(&format!(".{}", env::current_account_id()))
+            .and_then(|account_id| account_id.parse().ok())
+            .expect("Predecessor is not a sub-account");
+        let account = self
+            .accounts
+            .get_mut(&account_id)
+            .filter(|account| account.sub_account.as_deref() == Some(sub_account.as_str()))
+            .expect("Predecessor is not the sub-account of the account");
+
+        if let PromiseResult::Failed = env::promise_result(0) {
+            return;
+        }
+
+        account.sub_account = None;
+        account.credit(CustodyAsset::Near(balance));
+    }
+}
+
+impl AbstractAccountContract {
+    pub fn handle_sub_account_action(&mut self, account_id: AbstractAccountId, action: Action) {
+        let account = self.accounts.get_mut(&account_id).unwrap();
+
+        if let Action::CreateSubAccount { initial_balance } = action {
+            require!(
+                account.sub_account.is_none(),
+                "Account already has a sub-account"
+            );
+
+            let sub_account = format!("{}.{}", account_id, env::current_account_id());
+            // Checked before the initial balance is debited, NEAR account ids are 64 characters at most
+            require!(
+                sub_account.len() <= 64,
+                "Sub-account id exceeds 64 characters"
+            );
+            let sub_account: AccountId = sub_account
+                .parse()
+                .expect("Account id can't be used as a sub-account name");
+
+            account.debit(&CustodyAsset::Near(initial_balance));
+            account.sub_account = Some(sub_account.to_string());
+
+            let proxy_code = self.proxy_code.get().clone().expect("Proxy code not set");
+
+            Promise::new(sub_account)
+                .create_account()
+                .transfer(NearToken::from_yoctonear(initial_balance.0))
+                .deploy_contract(proxy_code)
+                .function_call(
+                    "new".to_string(),
+                    serde_json::json!({ "owner": env::current_account_id() })
+                        .to_string()
+                        .into_bytes(),
+                    NearToken::from_yoctonear(0),
+                    PROXY_INIT_GAS,
+                )
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(SUB_ACCOUNT_CALLBACK_GAS)
+                        .create_sub_account_callback(account_id, initial_balance),
+                );
+
+            return;
+        }
+
+        let sub_account: AccountId = account
+            .sub_account
+            .as_ref()
+            .expect("Account has no sub-account")
+            .parse()
+            .unwrap();
+        let proxy = ext_proxy::ext(sub_account);
+
+        match action {
+            Action::SubAccountFunctionCall {
+                receiver_id,
+                method_name,
+                args,
+                deposit,
+                gas,
+            } => {
+                proxy
+                    .with_static_gas(PROXY_GAS.saturating_add(Gas::from_gas(gas.0)))
+                    .function_call(
+                        receiver_id.parse().expect("Invalid receiver_id"),
+                        method_name,
+                        Base64VecU8::from(args.to_string().into_bytes()),
+                        deposit,
+                        U64(gas.0),
+                    );
+            }
+            Action::SubAccountTransfer {
+                receiver_id,
+                amount,
+            } => {
+                proxy
+                    .with_static_gas(PROXY_GAS)
+                    .transfer(receiver_id.parse().expect("Invalid receiver_id"), amount);
+            }
+            // The balance always comes back to this contract, a beneficiary that doesn't exist would burn it
+            Action::DeleteSubAccount => {
+                proxy
+                    .with_static_gas(PROXY_GAS.saturating_add(SUB_ACCOUNT_DELETED_GAS))
+                    .delete_account();
+            }
+            _ => env::panic_str("Invalid sub-account action"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::{context, full_access, setup_account};
+    use near_sdk::{test_vm_config, testing_env, RuntimeFeesConfig};
+
+    // An account whose alice.abstract.near proxy is being deleted
+    fn deleting_sub_account() -> (AbstractAccountContract, AbstractAccountId) {
+        let (mut contract, account_id) = setup_account(vec![full_access("bob")]);
+        contract.accounts.get_mut(&account_id).unwrap().sub_account =
+            Some("alice.abstract.near".to_string());
+
+        (contract, account_id)
+    }
+
+    fn from_proxy(results: Vec<PromiseResult>) {
+        testing_env!(
+            context()
+                .predecessor_account_id("alice.abstract.near".parse().unwrap())
+                .build(),
+            test_vm_config(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            results
+        );
+    }
+
+    #[test]
+    fn sub_account_deleted_should_credit_the_balance_to_the_account() {
+        let (mut contract, account_id) = deleting_sub_account();
+        from_proxy(vec![PromiseResult::Successful(vec![])]);
+
+        contract.sub_account_deleted(U128(5));
+
+        let account = contract.accounts.get(&account_id).unwrap();
+        assert!(account.sub_account.is_none());
+        assert_eq!(account.balance, U128(5));
+    }
+
+    #[test]
+    fn sub_account_deleted_should_keep_the_sub_account_if_the_deletion_failed() {
+        let (mut contract, account_id) = deleting_sub_account();
+        from_proxy(vec![PromiseResult::Failed]);
+
+        contract.sub_account_deleted(U128(5));
+
+        let account = contract.accounts.get(&account_id).unwrap();
+        assert!(account.sub_account.is_some());
+        assert_eq!(account.balance, U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only a deleted proxy can report its balance")]
+    fn sub_account_deleted_should_fail_for_direct_calls() {
+        let (mut contract, _) = deleting_sub_account();
+        from_proxy(vec![]);
+
+        contract.sub_account_deleted(U128(5));
+    }
+
+    #[test]
+    #[should_panic(expected = "Sub-account id exceeds 64 characters")]
+    fn create_sub_account_should_fail_for_long_account_ids() {
+        let (mut contract, _) = setup_account(vec![full_access("bob")]);
+        let account_id: AbstractAccountId = "a".repeat(60).parse().unwrap();
+        contract.add_account(account_id.clone(), full_access("carol"), None);
+
+        contract.handle_sub_account_action(
+            account_id,
+            Action::CreateSubAccount {
+                initial_balance: U128(0),
+            },
+        );
+    }
+}
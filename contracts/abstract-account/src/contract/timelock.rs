@@ -0,0 +1,226 @@
+use near_sdk::{env, json_types::U64, require, Promise};
+use near_sdk_contract_tools::nft::nep145::{Nep145, Nep145Controller};
+
+use crate::types::timelock::{PendingOperation, TimelockPolicy, MAX_TIMELOCK_DELAY};
+use crate::types::transaction::ActionKind;
+use crate::*;
+
+#[near]
+impl AbstractAccountContract {
+    /// Executes a queued operation once its delay has elapsed. Callable by anyone.
+    pub fn execute_pending_operation(
+        &mut self,
+        account_id: AbstractAccountId,
+        operation_id: u64,
+    ) -> Option<Promise> {
+        let account = self
+            .accounts
+            .get_mut(&account_id)
+            .expect("Account not found");
+        let index = account
+            .pending_operations
+            .iter()
+            .position(|operation| operation.id == operation_id)
+            .expect("Pending operation not found");
+
+        require!(
+            env::block_timestamp() >= account.pending_operations[index].executable_at.0,
+            "Pending operation delay has not elapsed"
+        );
+
+        let operation = account.pending_operations.remove(index);
+
+        // Removing a requester or downgrading it to limited permissions during the delay voids their operation
+        require!(
+            operation
+                .requested_by
+                .iter()
+                .all(|identity| account.has_full_access(identity)),
+            "Pending operation requester no longer has full access"
+        );
+
+        self.dispatch_transaction(
+            env::predecessor_account_id(),
+            operation.act_as,
+            operation.transaction,
+            None,
+        )
+    }
+
+    pub fn list_pending_operations(&self, account_id: AbstractAccountId) -> Vec<PendingOperation> {
+        self.accounts
+            .get(&account_id)
+            .map(|account| account.pending_operations.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl AbstractAccountContract {
+    /// Vetoes are exempt from the multisig and timelock policies, so a single identity can cancel an operation.
+    /// It must have full access and not be one of the operation requesters
+    ///
+    /// # Arguments
+    /// * `canceller` - The identity the vetoing transaction acts as, one of its signers
+    pub fn validate_veto(
+        &self,
+        account_id: &AbstractAccountId,
+        canceller: &Identity,
+        operation_id: u64,
+    ) {
+        let account = self.accounts.get(account_id).expect("Account not found");
+        let operation = account
+            .pending_operations
+            .iter()
+            .find(|operation| operation.id == operation_id)
+            .expect("Pending operation not found");
+
+        require!(
+            account.has_full_access(canceller),
+            "Only a full access identity can cancel a pending operation"
+        );
+        require!(
+            !operation.requested_by.contains(canceller),
+            "A pending operation can't be cancelled by its requester"
+        );
+    }
+
+    pub fn queue_operation(
+        &mut self,
+        predecessor: &AccountId,
+        requested_by: Vec<Identity>,
+        act_as: Identity,
+        transaction: Transaction,
+        delay: u64,
+    ) {
+        let storage_usage_start = env::storage_usage();
+        self.storage_balance_of(predecessor.clone())
+            .expect("Predecessor has not registered for storage");
+
+        let account = self
+            .accounts
+            .get_mut(&transaction.account_id)
+            .expect("Account not found");
+
+        let executable_at = env::block_timestamp().checked_add(delay);
+        require!(executable_at.is_some(), "Timelock delay overflow");
+
+        account.pending_operations.push(PendingOperation {
+            id: account.next_operation_id,
+            act_as,
+            transaction,
+            requested_by,
+            executable_at: U64(executable_at.unwrap()),
+        });
+        account.next_operation_id += 1;
+
+        self.accounts.flush();
+
+        self.storage_accounting(predecessor, storage_usage_start)
+            .expect("Storage accounting failed");
+    }
+}
+
+pub fn validate_timelock_policy(policy: &TimelockPolicy) {
+    require!(
+        policy.delay.0 <= MAX_TIMELOCK_DELAY,
+        "Timelock delay can't exceed 30 days"
+    );
+    require!(
+        !policy.actions.contains(&ActionKind::CancelPendingOperation),
+        "Vetoes can't be timelocked"
+    );
+    require!(
+        !policy.actions.contains(&ActionKind::CancelRecovery),
+        "Recovery cancellations can't be timelocked"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::identity::IdentityPermissions;
+    use crate::utils::test_utils::{
+        context, full_access, identity, setup_account, transaction, CONTRACT_ID,
+    };
+    use near_sdk::testing_env;
+
+    fn setup_operation() -> (AbstractAccountContract, AbstractAccountId) {
+        let (mut contract, account_id) = setup_account(vec![
+            full_access("bob"),
+            full_access("carol"),
+            IdentityWithPermissions {
+                identity: identity("dave"),
+                permissions: Some(IdentityPermissions {
+                    enable_act_as: true,
+                }),
+            },
+        ]);
+        contract.queue_operation(
+            &CONTRACT_ID.parse().unwrap(),
+            vec![identity("bob")],
+            identity("bob"),
+            transaction(&account_id, vec![Action::RemoveIdentity(identity("carol"))]),
+            100,
+        );
+
+        (contract, account_id)
+    }
+
+    #[test]
+    fn validate_veto_should_accept_another_full_access_identity() {
+        let (contract, account_id) = setup_operation();
+
+        contract.validate_veto(&account_id, &identity("carol"), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "A pending operation can't be cancelled by its requester")]
+    fn validate_veto_should_reject_the_requester() {
+        let (contract, account_id) = setup_operation();
+
+        contract.validate_veto(&account_id, &identity("bob"), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only a full access identity can cancel a pending operation")]
+    fn validate_veto_should_reject_a_limited_identity() {
+        let (contract, account_id) = setup_operation();
+
+        contract.validate_veto(&account_id, &identity("dave"), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Pending operation requester no longer has full access")]
+    fn execute_pending_operation_should_fail_after_the_requester_was_downgraded() {
+        let (mut contract, account_id) = setup_operation();
+        let account = contract.accounts.get_mut(&account_id).unwrap();
+        account.remove_identity(&identity("bob"));
+        account.add_identity(IdentityWithPermissions {
+            identity: identity("bob"),
+            permissions: Some(IdentityPermissions {
+                enable_act_as: true,
+            }),
+        });
+        testing_env!(context().block_timestamp(100).build());
+
+        contract.execute_pending_operation(account_id, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Recovery cancellations can't be timelocked")]
+    fn validate_timelock_policy_should_reject_listed_recovery_cancellations() {
+        validate_timelock_policy(&TimelockPolicy {
+            delay: U64(1),
+            actions: vec![ActionKind::CancelRecovery],
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Vetoes can't be timelocked")]
+    fn validate_timelock_policy_should_reject_listed_vetoes() {
+        validate_timelock_policy(&TimelockPolicy {
+            delay: U64(1),
+            actions: vec![ActionKind::CancelPendingOperation],
+        });
+    }
+}
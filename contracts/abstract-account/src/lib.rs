@@ -3,42 +3,79 @@ mod mods;
 mod types;
 mod utils;
 
-use interfaces::{auth::wallet::WalletType, traits::signable_message::SignableMessage};
+use interfaces::traits::signable_message::{EncodedMessage, MessageEncoding, SignableMessage};
 use near_sdk::{
-    env, near,
+    env,
+    json_types::U128,
+    near, require,
     serde::{Deserialize, Serialize},
-    store::IterableMap,
-    AccountId, Promise,
+    store::{IterableMap, IterableSet, LazyOption, LookupMap, LookupSet},
+    AccountId, NearToken, Promise, PromiseError, PromiseResult,
 };
 use near_sdk_contract_tools::Nep145;
 use schemars::JsonSchema;
+use serde_json::Value;
 use types::{
     account::Account,
+    account_id::AbstractAccountId,
+    domain::{Domain, MESSAGE_VERSION},
+    event::AbstractAccountEvent,
     identity::{Identity, IdentityWithPermissions},
-    transaction::{Transaction, UserOp},
+    multisig::Proposal,
+    paymaster::{Sponsor, Sponsorship},
+    signer::{SignCall, SignerConfig, SignerQuote},
+    transaction::{SignPayloadsRequest, Transaction, UserOp, UserOpOutcome},
 };
 use types::{identity::AuthTypeNames, transaction::Action};
+use utils::utils::promise_result_as_bool;
 
 const KEY_PREFIX_ACCOUNTS: &[u8] = b"q";
 const KEY_PREFIX_AUTH_CONTRACTS: &[u8] = b"a";
+const KEY_PREFIX_PROPOSALS: &[u8] = b"p";
+const KEY_PREFIX_SPONSORS: &[u8] = b"s";
+const KEY_PREFIX_SIGNERS: &[u8] = b"g";
+const KEY_PREFIX_PROXY_CODE: &[u8] = b"x";
+const KEY_PREFIX_RESERVED_ACCOUNT_IDS: &[u8] = b"r";
+const KEY_PREFIX_IDENTITY_ACCOUNTS: &[u8] = b"i";
+// Followed by the identity index key, one set per identity
+const KEY_PREFIX_IDENTITY_ACCOUNT_IDS: &[u8] = b"j";
+const KEY_PREFIX_ACCOUNT_PROPOSALS: &[u8] = b"k";
+// Followed by the account id hash, one set per account
+const KEY_PREFIX_ACCOUNT_PROPOSAL_IDS: &[u8] = b"l";
 
 #[derive(Nep145)]
 #[near(contract_state)]
 pub struct AbstractAccountContract {
-    accounts: IterableMap<String, Account>,
+    accounts: IterableMap<AbstractAccountId, Account>,
     auth_contracts: IterableMap<AuthTypeNames, AccountId>,
+    // Default signer of the Sign actions that don't choose one, always registered in signers
     signer_account: AccountId,
     /*
-    Tracks the maximum nonce of the accounts deleted.
+    Incremented on every account creation and assigned as the new account epoch.
 
-    When an account is deleted and recreated, its nonce would normally reset to 0.
-    This would allow previously used signatures (with nonces 0 through N) to be
-    replayed on the new account.
-
-    By tracking the global maximum nonce, we ensure that even recreated accounts
-    start with the max_nonce avoiding replay attacks.
+    When an account is deleted and recreated, its nonce resets to 0. Since the epoch
+    is part of every signed message, signatures from a previous incarnation of the
+    same account_id can't be replayed on the new one, whatever their nonce.
     */
-    max_nonce: u128,
+    account_epoch: u64,
+    // NEAR network the contract is deployed to, part of the domain of every signed message
+    network_id: String,
+    // Transactions waiting for M of N approvals, keyed by transaction hash
+    proposals: IterableMap<String, Proposal>,
+    // Balances and policies of the accounts paying for user operations submitted by relayers
+    sponsors: IterableMap<AccountId, Sponsor>,
+    // Signer contracts Sign actions can be sent to, managed by the contract account
+    signers: IterableMap<AccountId, SignerConfig>,
+    // Wasm of the proxy contract deployed to sub-accounts, stored apart so it's only read when deploying
+    proxy_code: LazyOption<Vec<u8>>,
+    // Account ids that can't be created, managed by the contract account
+    reserved_account_ids: LookupSet<AbstractAccountId>,
+    // When set, add_account must prove the creator possesses the identity, so account ids can't be squatted
+    creation_proof_required: bool,
+    // Accounts of every identity, keyed by Identity::index_key so lookups don't scan the accounts
+    identity_accounts: LookupMap<[u8; 32], IterableSet<AbstractAccountId>>,
+    // Pending proposal ids of every account, so listing them doesn't scan the proposals
+    account_proposals: LookupMap<AbstractAccountId, IterableSet<String>>,
 }
 
 #[derive(Deserialize, Serialize, JsonSchema)]
@@ -54,7 +91,16 @@ impl Default for AbstractAccountContract {
             accounts: IterableMap::new(KEY_PREFIX_ACCOUNTS),
             auth_contracts: IterableMap::new(KEY_PREFIX_AUTH_CONTRACTS),
             signer_account: env::current_account_id(),
-            max_nonce: 0,
+            account_epoch: 0,
+            network_id: String::new(),
+            proposals: IterableMap::new(KEY_PREFIX_PROPOSALS),
+            sponsors: IterableMap::new(KEY_PREFIX_SPONSORS),
+            signers: IterableMap::new(KEY_PREFIX_SIGNERS),
+            proxy_code: LazyOption::new(KEY_PREFIX_PROXY_CODE, None),
+            reserved_account_ids: LookupSet::new(KEY_PREFIX_RESERVED_ACCOUNT_IDS),
+            creation_proof_required: false,
+            identity_accounts: LookupMap::new(KEY_PREFIX_IDENTITY_ACCOUNTS),
+            account_proposals: LookupMap::new(KEY_PREFIX_ACCOUNT_PROPOSALS),
         }
     }
 }
@@ -62,7 +108,11 @@ impl Default for AbstractAccountContract {
 #[near]
 impl AbstractAccountContract {
     #[init]
-    pub fn new(auth_contracts: Vec<AuthContractConfig>, signer_account: String) -> Self {
+    pub fn new(
+        auth_contracts: Vec<AuthContractConfig>,
+        signer_account: String,
+        network_id: String,
+    ) -> Self {
         let mut contract = Self::default();
 
         for contract_config in auth_contracts {
@@ -73,70 +123,1012 @@ impl AbstractAccountContract {
         }
 
         contract.signer_account = signer_account.parse().unwrap();
+        contract
+            .signers
+            .insert(contract.signer_account.clone(), SignerConfig::default());
+        contract.network_id = network_id;
 
         contract
     }
 
     #[payable]
     pub fn auth(&mut self, user_op: UserOp) -> Promise {
-        let predecessor = env::predecessor_account_id();
-        self.validate_permission_and_account(&user_op);
+        self.authenticate(
+            user_op,
+            env::predecessor_account_id(),
+            env::attached_deposit(),
+            None,
+        )
+    }
+
+    /// Records an authenticated user operation: consumes its nonce and approves its proposal or queues it, depending on
+    /// the account policies. A rejected operation records nothing, and auth_result_callback refunds it
+    ///
+    /// # Returns
+    /// Whether the operation was rejected, recorded for later or approved for execution
+    #[private]
+    pub fn auth_callback(
+        &mut self,
+        signers: Vec<Identity>,
+        act_as: Option<Identity>,
+        transaction: Transaction,
+        predecessor: AccountId,
+        is_pending: bool,
+    ) -> UserOpOutcome {
+        // Results of the joined verifications arrive in the same order as the signers
+        let verified_signers: Vec<Identity> = signers
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| promise_result_as_bool(*index as u64))
+            .map(|(_, signer)| signer)
+            .collect();
+
+        if verified_signers.is_empty() {
+            return UserOpOutcome::rejected("Authentication failed");
+        }
+
+        let Some(account) = self.accounts.get(&transaction.account_id) else {
+            return UserOpOutcome::rejected("Account not found");
+        };
 
-        let account_id = user_op.transaction.account_id.clone();
-        let account = self.accounts.get_mut(&account_id).unwrap();
+        if account.epoch != transaction.epoch {
+            return UserOpOutcome::rejected("Account epoch mismatch");
+        }
+
+        // Another operation may have consumed the nonce while the credentials were being verified
+        if !is_pending && account.nonce(transaction.nonce_key) != transaction.nonce {
+            return UserOpOutcome::rejected("Nonce already used");
+        }
+
+        // The veto is checked against the identity the transaction acts as, so it must be one of the signers
+        if transaction.cancels_pending_operation()
+            && act_as
+                .as_ref()
+                .is_some_and(|act_as| !verified_signers.contains(act_as))
+        {
+            return UserOpOutcome::rejected("A veto must act as one of its signers");
+        }
+
+        if !is_pending && account.required_approvals(&transaction.actions) <= 1 {
+            if transaction.requires_full_access()
+                && !verified_signers
+                    .iter()
+                    .any(|signer| account.has_full_access(signer))
+            {
+                return UserOpOutcome::rejected("Action requires a full access identity");
+            }
 
-        account.nonce += 1;
+            self.consume_nonce(&predecessor, &transaction.account_id, transaction.nonce_key);
 
-        let mut identity = user_op.auth.identity.clone();
-        identity.inject_webauthn_compressed_public_key(account);
+            let act_as = act_as.unwrap_or_else(|| verified_signers[0].clone());
+            return self.schedule_transaction(&predecessor, verified_signers, act_as, transaction);
+        }
 
-        let act_as = if let Some(act_as) = user_op.act_as {
-            act_as
-        } else {
-            identity.clone()
+        // The approval is validated before the nonce is consumed, so a rejected approval doesn't consume it
+        let proposal = match self.approve_proposal(
+            &predecessor,
+            verified_signers,
+            act_as,
+            transaction.clone(),
+            is_pending,
+        ) {
+            Ok(proposal) => proposal,
+            Err(reason) => return UserOpOutcome::Rejected(reason),
         };
 
-        let transaction = user_op.transaction;
-        let signed_message = transaction.to_signed_message(());
+        if !is_pending {
+            self.consume_nonce(&predecessor, &transaction.account_id, transaction.nonce_key);
+        }
 
-        self.validate_credentials(
-            identity,
-            user_op.auth.credentials,
-            signed_message,
+        let Some(proposal) = proposal else {
+            return UserOpOutcome::Pending;
+        };
+        let act_as = proposal
+            .act_as
+            .unwrap_or_else(|| proposal.approvals[0].clone());
+
+        self.schedule_transaction(
+            &predecessor,
+            proposal.approvals,
+            act_as,
+            proposal.transaction,
+        )
+    }
+
+    /// Settles the funds of an authenticated user operation. The relayer is reimbursed and the deposit credited to the
+    /// account once the operation is recorded, otherwise its deposit, or its whole cost to the sponsor, is refunded.
+    /// The callback doesn't panic, so the settlement isn't reverted by the actions, which are executed in their own receipt
+    ///
+    /// # Arguments
+    /// * `predecessor` - Account charged for the storage used by the operation, refunded when it's rejected
+    /// * `transaction` - The authenticated transaction
+    /// * `deposit` - Deposit attached to the operation, held by the contract until it's settled
+    /// * `sponsorship` - Cost reserved from a sponsor balance, for user operations submitted by relayers
+    #[private]
+    pub fn auth_result_callback(
+        &mut self,
+        predecessor: AccountId,
+        transaction: Transaction,
+        deposit: U128,
+        sponsorship: Option<Sponsorship>,
+        #[callback_result] outcome: Result<UserOpOutcome, PromiseError>,
+    ) -> Option<Promise> {
+        let act_as = match outcome {
+            Ok(UserOpOutcome::Rejected(reason)) => {
+                return self.reject_transaction(
+                    predecessor,
+                    &transaction,
+                    &reason,
+                    deposit,
+                    sponsorship,
+                );
+            }
+            // auth_callback only panics when the operation can't be recorded, e.g. for lack of storage balance
+            Err(_) => {
+                return self.reject_transaction(
+                    predecessor,
+                    &transaction,
+                    "Failed to record the user operation",
+                    deposit,
+                    sponsorship,
+                );
+            }
+            Ok(UserOpOutcome::Pending) => None,
+            Ok(UserOpOutcome::Approved(act_as)) => Some(act_as),
+        };
+
+        if let Some(ref sponsorship) = sponsorship {
+            self.reimburse_relayer(sponsorship);
+        }
+
+        // The deposit of an authenticated operation funds the account, e.g. for signature fees.
+        // RemoveAccount requires an empty balance, so the deposit of an operation removing the account is refunded
+        match self.accounts.get_mut(&transaction.account_id) {
+            Some(account) if !transaction.removes_account() => {
+                account.balance = U128(account.balance.0 + deposit.0);
+            }
+            // The account may also have been removed while the operation was being recorded
+            _ if deposit.0 > 0 => {
+                Promise::new(predecessor.clone()).transfer(NearToken::from_yoctonear(deposit.0));
+            }
+            _ => {}
+        }
+
+        let act_as = act_as?;
+        let (account_id, nonce_key, nonce) = (
+            transaction.account_id.clone(),
+            transaction.nonce_key,
+            U128(transaction.nonce),
+        );
+
+        Some(
             Self::ext(env::current_account_id())
-                .with_attached_deposit(env::attached_deposit())
-                .auth_callback(account_id, act_as, transaction, predecessor),
+                .execute_user_op(predecessor, act_as, transaction, sponsorship)
+                .then(
+                    Self::ext(env::current_account_id())
+                        .execute_user_op_callback(account_id, nonce_key, nonce),
+                ),
         )
     }
 
-    #[payable]
+    /// Executes the actions of a recorded user operation, in a receipt of their own so a failing action doesn't revert the settlement
     #[private]
-    pub fn auth_callback(
+    pub fn execute_user_op(
+        &mut self,
+        predecessor: AccountId,
+        act_as: Identity,
+        transaction: Transaction,
+        sponsorship: Option<Sponsorship>,
+    ) -> Option<Promise> {
+        self.dispatch_transaction(predecessor, act_as, transaction, sponsorship)
+    }
+
+    /// Records the failure of the actions, or returns their result, e.g. the signatures
+    #[private]
+    pub fn execute_user_op_callback(
+        &self,
+        account_id: AbstractAccountId,
+        nonce_key: u64,
+        nonce: U128,
+    ) {
+        match env::promise_result(0) {
+            PromiseResult::Successful(value) => env::value_return(&value),
+            PromiseResult::Failed => AbstractAccountEvent::ExecutionFailed {
+                account_id,
+                nonce_key,
+                nonce,
+                reason: "Actions failed".to_string(),
+            }
+            .emit(),
+        }
+    }
+
+    /// Executes the transaction actions once the credentials of every AddIdentityWithAuth action were verified
+    /// and, if the transaction signs payloads, every signer quoted its fee and latest key version
+    #[private]
+    pub fn execute_actions_callback(
         &mut self,
-        account_id: String,
+        predecessor: AccountId,
         identity: Identity,
         transaction: Transaction,
+        signers: Vec<AccountId>,
+        sponsorship: Option<Sponsorship>,
+    ) -> Option<Promise> {
+        // The signer quotes are joined after the credential verifications, two results per signer
+        let verifications_count = env::promise_results_count() - 2 * signers.len() as u64;
+
+        if !(0..verifications_count).all(promise_result_as_bool) {
+            return self.reject_execution(&transaction, "Authentication failed");
+        }
+
+        let quotes = Self::read_signer_quotes(signers, verifications_count);
+
+        self.execute_actions(predecessor, identity, transaction, quotes, sponsorship)
+    }
+}
+
+impl AbstractAccountContract {
+    /// Verifies the user operation credentials, records it in auth_callback and settles it in auth_result_callback
+    ///
+    /// # Arguments
+    /// * `user_op` - The user operation to authenticate
+    /// * `predecessor` - Account charged for the storage used by the operation
+    /// * `deposit` - Deposit available to the operation actions
+    /// * `sponsorship` - Cost reserved from a sponsor balance, for user operations submitted by relayers
+    fn authenticate(
+        &mut self,
+        user_op: UserOp,
+        predecessor: AccountId,
+        deposit: NearToken,
+        sponsorship: Option<Sponsorship>,
+    ) -> Promise {
+        // Approvals of a pending proposal sign the same transaction, so its nonce was already consumed
+        let is_pending = self
+            .proposals
+            .get(&user_op.transaction.hash())
+            .is_some_and(|proposal| !proposal.is_expired());
+        self.validate_permission_and_account(&user_op, is_pending);
+
+        // The nonce is consumed in auth_callback, once the credentials are verified
+        let account = self.accounts.get(&user_op.transaction.account_id).unwrap();
+
+        let transaction = user_op.transaction;
+
+        let auths: Vec<(Identity, Value, EncodedMessage)> = user_op
+            .auth
+            .into_auths()
+            .into_iter()
+            .map(|auth| {
+                let mut identity = auth.identity;
+                identity.inject_webauthn_compressed_public_key(account);
+
+                let encoding = auth
+                    .encoding
+                    .unwrap_or_else(|| identity.default_message_encoding());
+                let signed_message = transaction.encode_message(&self.domain(encoding), encoding);
+
+                (identity, auth.credentials, signed_message)
+            })
+            .collect();
+        let signers = auths
+            .iter()
+            .map(|(identity, _, _)| identity.clone())
+            .collect();
+
+        // The deposit stays with the contract until auth_result_callback settles the operation
+        self.validate_batch_credentials(
+            auths,
+            Self::ext(env::current_account_id()).auth_callback(
+                signers,
+                user_op.act_as,
+                transaction.clone(),
+                predecessor.clone(),
+                is_pending,
+            ),
+        )
+        .then(Self::ext(env::current_account_id()).auth_result_callback(
+            predecessor,
+            transaction,
+            U128(deposit.as_yoctonear()),
+            sponsorship,
+        ))
+    }
+
+    pub fn domain(&self, encoding: MessageEncoding) -> Domain {
+        Domain {
+            contract_id: env::current_account_id().to_string(),
+            network_id: self.network_id.clone(),
+            version: MESSAGE_VERSION,
+            encoding,
+        }
+    }
+
+    /// Records a user operation that failed after its credentials were verified and refunds its deposit,
+    /// or its whole cost to the sponsor. The callback doesn't panic, so the refund and the event aren't reverted
+    fn reject_transaction(
+        &mut self,
+        predecessor: AccountId,
+        transaction: &Transaction,
+        reason: &str,
+        deposit: U128,
+        sponsorship: Option<Sponsorship>,
+    ) -> Option<Promise> {
+        AbstractAccountEvent::AuthFailed {
+            account_id: transaction.account_id.clone(),
+            nonce_key: transaction.nonce_key,
+            nonce: U128(transaction.nonce),
+            reason: reason.to_string(),
+        }
+        .emit();
+
+        if let Some(sponsorship) = sponsorship {
+            self.refund_sponsor(&sponsorship);
+            return None;
+        }
+
+        if deposit.0 == 0 {
+            return None;
+        }
+
+        Some(Promise::new(predecessor).transfer(NearToken::from_yoctonear(deposit.0)))
+    }
+
+    /// Reports a recorded user operation whose actions were rejected without panicking, none of them is applied
+    fn reject_execution(&self, transaction: &Transaction, reason: &str) -> Option<Promise> {
+        AbstractAccountEvent::ExecutionFailed {
+            account_id: transaction.account_id.clone(),
+            nonce_key: transaction.nonce_key,
+            nonce: U128(transaction.nonce),
+            reason: reason.to_string(),
+        }
+        .emit();
+
+        None
+    }
+
+    /// Approves an authorized transaction for execution, or queues it when the account timelock policy applies
+    fn schedule_transaction(
+        &mut self,
+        predecessor: &AccountId,
+        signers: Vec<Identity>,
+        act_as: Identity,
+        transaction: Transaction,
+    ) -> UserOpOutcome {
+        let account = self
+            .accounts
+            .get(&transaction.account_id)
+            .expect("Account not found");
+
+        if let Some(delay) = account.timelock_delay(&transaction.actions) {
+            self.queue_operation(predecessor, signers, act_as, transaction, delay);
+            return UserOpOutcome::Pending;
+        }
+
+        UserOpOutcome::Approved(act_as)
+    }
+
+    /// Dispatches the transaction actions, verifying first the credentials of the identities being added
+    /// so the batch is only applied if all of them are valid
+    ///
+    /// # Arguments
+    /// * `sponsorship` - Sponsor paying the signature fees of the actions, up to its max_fee
+    pub fn dispatch_transaction(
+        &mut self,
+        predecessor: AccountId,
+        identity: Identity,
+        mut transaction: Transaction,
+        sponsorship: Option<Sponsorship>,
+    ) -> Option<Promise> {
+        let signers = self.pin_signers(&mut transaction);
+
+        let account = self
+            .accounts
+            .get(&transaction.account_id)
+            .expect("Account not found");
+
+        let identity_auths: Vec<(Identity, Value, EncodedMessage)> = transaction
+            .actions
+            .iter()
+            .filter_map(|action| match action {
+                Action::AddIdentityWithAuth(auth) => {
+                    let mut identity = auth.identity_with_permissions.identity.clone();
+                    identity.inject_webauthn_compressed_public_key(account);
+
+                    let encoding = auth
+                        .encoding
+                        .unwrap_or_else(|| identity.default_message_encoding());
+                    let signed_message =
+                        action.encode_message((&self.domain(encoding), &transaction), encoding);
+
+                    Some((identity, auth.credentials.clone(), signed_message))
+                }
+                _ => None,
+            })
+            .collect();
+
+        // Sign actions are paid from the account balance at the fee quoted by their signer
+        let signer_quotes = self.quote_signers(&signers);
+
+        let Some(promise) = self
+            .verify_batch_credentials(identity_auths)
+            .into_iter()
+            .chain(signer_quotes)
+            .reduce(|joined, promise| joined.and(promise))
+        else {
+            return self.execute_actions(predecessor, identity, transaction, vec![], sponsorship);
+        };
+
+        Some(promise.then(
+            Self::ext(env::current_account_id()).execute_actions_callback(
+                predecessor,
+                identity,
+                transaction,
+                signers,
+                sponsorship,
+            ),
+        ))
+    }
+
+    /// Applies the actions in the order given within the current receipt, so a failing action reverts all of them.
+    /// A Sign action is paid when it's reached, its fees charged to the sponsor up to its max_fee and then debited
+    /// from the account balance, and signed under the signing root the account has at that point.
+    /// The sign requests are dispatched together once every action succeeded
+    fn execute_actions(
+        &mut self,
         predecessor: AccountId,
-        #[callback_result] auth_result: Result<bool, near_sdk::PromiseError>,
+        identity: Identity,
+        transaction: Transaction,
+        quotes: Vec<SignerQuote>,
+        sponsorship: Option<Sponsorship>,
     ) -> Option<Promise> {
-        match auth_result {
-            Ok(true) => {
-                match transaction.action {
-                    Action::Sign(sign_payloads_request) => {
-                        return Some(self.sign(identity, sign_payloads_request));
-                    }
-                    action @ (Action::RemoveAccount
-                    | Action::AddIdentity(_)
-                    | Action::AddIdentityWithAuth(_)
-                    | Action::RemoveIdentity(_)) => {
-                        self.handle_account_action(predecessor, account_id, action);
-                    }
-                };
-
-                None
+        let mut sign_calls = vec![];
+        let mut sponsored_fee = None;
+
+        for action in transaction.actions {
+            match action {
+                Action::Sign(sign_payloads_request) => {
+                    let calls = self.sign_calls(
+                        &transaction.account_id,
+                        &identity,
+                        sign_payloads_request,
+                        &quotes,
+                    );
+                    let fee: u128 = calls.iter().map(|call| call.deposit.as_yoctonear()).sum();
+
+                    // Sponsored fees are paid directly from the sponsor balance, so they never become withdrawable by the account
+                    let account_fee = fee
+                        - sponsorship.as_ref().map_or(0, |sponsorship| {
+                            self.charge_sponsored_fee(sponsorship, &mut sponsored_fee, fee)
+                        });
+
+                    let account = self.accounts.get_mut(&transaction.account_id).unwrap();
+                    require!(
+                        account.balance.0 >= account_fee,
+                        "Insufficient account balance to pay the signature fees"
+                    );
+                    account.balance = U128(account.balance.0 - account_fee);
+
+                    sign_calls.extend(calls);
+                }
+                Action::CancelPendingOperation(operation_id) => {
+                    self.validate_veto(&transaction.account_id, &identity, operation_id);
+                    self.handle_account_action(
+                        predecessor.clone(),
+                        transaction.account_id.clone(),
+                        Action::CancelPendingOperation(operation_id),
+                    );
+                }
+                action => {
+                    self.handle_account_action(
+                        predecessor.clone(),
+                        transaction.account_id.clone(),
+                        action,
+                    );
+                }
             }
-            Ok(false) => env::panic_str("Authentication failed"),
-            Err(_) => env::panic_str("Error validating authentication"),
         }
+
+        if sign_calls.is_empty() {
+            return None;
+        }
+
+        Some(self.sign(transaction.account_id, sign_calls, sponsored_fee))
+    }
+
+    /// Builds the signature requests of a Sign action, at the fee quoted by their signer
+    fn sign_calls(
+        &self,
+        account_id: &AbstractAccountId,
+        identity: &Identity,
+        request: SignPayloadsRequest,
+        quotes: &[SignerQuote],
+    ) -> Vec<SignCall> {
+        // Earlier actions may have removed the account or the identity the requests are signed for
+        let account = self.accounts.get(account_id).expect("Account not found");
+        let signing_root = account.signing_root(account_id, Some(identity));
+
+        // Signers were pinned when the transaction was dispatched
+        let (signer_id, config) = self.resolve_signer(request.contract_id.as_ref());
+        let quote = quotes
+            .iter()
+            .find(|quote| quote.signer_id == signer_id)
+            .expect("Signer quote not found");
+        let scheme = request.scheme();
+
+        require!(
+            !request.payloads.is_empty(),
+            "At least one payload is required"
+        );
+
+        request
+            .payloads
+            .into_iter()
+            .map(|payload| {
+                require!(
+                    payload.key_version <= quote.latest_key_version,
+                    "Key version not supported by the signer"
+                );
+
+                SignCall {
+                    signer_id: signer_id.clone(),
+                    version: config.version,
+                    scheme,
+                    signing_root: signing_root.clone(),
+                    request: payload,
+                    deposit: quote.deposit,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mods::signer::SignRequest;
+    use crate::types::identity::IdentityPermissions;
+    use crate::types::multisig::MultisigPolicy;
+    use crate::types::paymaster::SponsorPolicy;
+    use crate::types::timelock::TimelockPolicy;
+    use crate::types::transaction::ActionKind;
+    use crate::utils::test_utils::{
+        full_access, identity, set_promise_results, setup_account, transaction, CONTRACT_ID,
+        SIGNER_ID,
+    };
+    use near_sdk::{json_types::U64, test_utils::get_logs};
+
+    const SIGN_FEE: u128 = 1;
+
+    fn sign() -> Action {
+        Action::Sign(SignPayloadsRequest {
+            contract_id: None,
+            scheme: None,
+            payloads: vec![SignRequest {
+                payload: vec![0; 32],
+                path: "ethereum,1".to_string(),
+                key_version: 0,
+            }],
+        })
+    }
+
+    fn verified(results: Vec<bool>) {
+        set_promise_results(
+            results
+                .into_iter()
+                .map(|result| PromiseResult::Successful(serde_json::to_vec(&result).unwrap()))
+                .collect(),
+        );
+    }
+
+    fn auth_callback(
+        contract: &mut AbstractAccountContract,
+        signers: Vec<Identity>,
+        transaction: Transaction,
+    ) -> UserOpOutcome {
+        contract.auth_callback(
+            signers,
+            None,
+            transaction,
+            CONTRACT_ID.parse().unwrap(),
+            false,
+        )
+    }
+
+    fn sponsorship(
+        contract: &mut AbstractAccountContract,
+        account_id: &AbstractAccountId,
+    ) -> Sponsorship {
+        let mut sponsor = Sponsor::new(SponsorPolicy {
+            accounts: vec![account_id.clone()],
+            actions: None,
+            daily_cap: U128(100),
+            gas_per_action: None,
+            max_fee_per_op: U128(0),
+        });
+        sponsor.balance = U128(90);
+        sponsor.spent_today = U128(10);
+        contract
+            .sponsors
+            .insert("sponsor.near".parse().unwrap(), sponsor);
+
+        Sponsorship {
+            sponsor_id: "sponsor.near".parse().unwrap(),
+            relayer: "relayer.near".parse().unwrap(),
+            day: 0,
+            gas_fee: U128(10),
+            max_fee: U128(0),
+        }
+    }
+
+    fn auth_failed_reason() -> Option<String> {
+        get_logs().iter().find_map(|log| {
+            let event: Value = serde_json::from_str(log.strip_prefix("EVENT_JSON:")?).ok()?;
+            (event["event"] == "auth_failed")
+                .then(|| event["data"]["reason"].as_str().unwrap().to_string())
+        })
+    }
+
+    fn quotes() -> Vec<SignerQuote> {
+        vec![SignerQuote {
+            signer_id: SIGNER_ID.parse().unwrap(),
+            deposit: NearToken::from_yoctonear(SIGN_FEE),
+            latest_key_version: 0,
+        }]
+    }
+
+    #[test]
+    fn execute_actions_should_sign_with_the_state_at_the_sign_action() {
+        let (mut contract, account_id) =
+            setup_account(vec![full_access("bob"), full_access("carol")]);
+        contract.accounts.get_mut(&account_id).unwrap().balance = U128(SIGN_FEE);
+
+        // The act_as identity is removed after the Sign action, which is still signed under its path
+        let promise = contract.execute_actions(
+            CONTRACT_ID.parse().unwrap(),
+            identity("bob"),
+            transaction(
+                &account_id,
+                vec![sign(), Action::RemoveIdentity(identity("bob"))],
+            ),
+            quotes(),
+            None,
+        );
+
+        assert!(promise.is_some());
+        let account = contract.accounts.get(&account_id).unwrap();
+        assert_eq!(account.balance, U128(0));
+        assert!(account.get_identity(&identity("bob")).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient account balance to pay the signature fees")]
+    fn execute_actions_should_pay_the_sign_fees_after_earlier_actions() {
+        let (mut contract, account_id) =
+            setup_account(vec![full_access("bob"), full_access("carol")]);
+        contract.accounts.get_mut(&account_id).unwrap().balance = U128(SIGN_FEE);
+
+        contract.execute_actions(
+            CONTRACT_ID.parse().unwrap(),
+            identity("bob"),
+            transaction(
+                &account_id,
+                vec![
+                    Action::Withdraw {
+                        receiver_id: "bob.near".to_string(),
+                        amount: U128(SIGN_FEE),
+                    },
+                    sign(),
+                ],
+            ),
+            quotes(),
+            None,
+        );
+    }
+
+    #[test]
+    fn auth_callback_should_approve_the_verified_signers_and_consume_the_nonce() {
+        let (mut contract, account_id) =
+            setup_account(vec![full_access("bob"), full_access("carol")]);
+        verified(vec![false, true]);
+
+        let outcome = auth_callback(
+            &mut contract,
+            vec![identity("bob"), identity("carol")],
+            transaction(&account_id, vec![Action::RemoveIdentity(identity("bob"))]),
+        );
+
+        // bob failed verification, so the operation acts as carol
+        assert!(matches!(outcome, UserOpOutcome::Approved(act_as) if act_as == identity("carol")));
+        assert_eq!(contract.accounts.get(&account_id).unwrap().nonce(0), 1);
+    }
+
+    #[test]
+    fn auth_callback_should_treat_failed_verifiers_as_unverified() {
+        let (mut contract, account_id) =
+            setup_account(vec![full_access("bob"), full_access("carol")]);
+        // A panicking verifier and one returning something else than a boolean
+        set_promise_results(vec![
+            PromiseResult::Failed,
+            PromiseResult::Successful(b"\"true\"".to_vec()),
+        ]);
+
+        let outcome = auth_callback(
+            &mut contract,
+            vec![identity("bob"), identity("carol")],
+            transaction(&account_id, vec![]),
+        );
+
+        assert!(
+            matches!(outcome, UserOpOutcome::Rejected(reason) if reason == "Authentication failed")
+        );
+        assert_eq!(contract.accounts.get(&account_id).unwrap().nonce(0), 0);
+    }
+
+    #[test]
+    fn auth_callback_should_only_count_the_verified_signers_as_approvals() {
+        let (mut contract, account_id) =
+            setup_account(vec![full_access("bob"), full_access("carol")]);
+        contract
+            .accounts
+            .get_mut(&account_id)
+            .unwrap()
+            .multisig_policy = Some(MultisigPolicy {
+            threshold: 2,
+            actions: None,
+        });
+        verified(vec![true, false]);
+
+        let transaction = transaction(&account_id, vec![Action::RemoveIdentity(identity("bob"))]);
+        let outcome = auth_callback(
+            &mut contract,
+            vec![identity("bob"), identity("carol")],
+            transaction.clone(),
+        );
+
+        let proposal = contract.proposals.get(&transaction.hash()).unwrap();
+        assert!(matches!(outcome, UserOpOutcome::Pending));
+        assert_eq!(proposal.approvals, vec![identity("bob")]);
+    }
+
+    #[test]
+    fn auth_callback_should_not_consume_the_nonce_when_rejected() {
+        let (mut contract, account_id) =
+            setup_account(vec![full_access("bob"), full_access("carol")]);
+        contract
+            .accounts
+            .get_mut(&account_id)
+            .unwrap()
+            .add_identity(IdentityWithPermissions {
+                identity: identity("dave"),
+                permissions: Some(IdentityPermissions {
+                    enable_act_as: true,
+                }),
+            });
+
+        verified(vec![false]);
+        let outcome = auth_callback(
+            &mut contract,
+            vec![identity("bob")],
+            transaction(&account_id, vec![]),
+        );
+        assert!(
+            matches!(outcome, UserOpOutcome::Rejected(reason) if reason == "Authentication failed")
+        );
+
+        verified(vec![true]);
+        let outcome = auth_callback(
+            &mut contract,
+            vec![identity("dave")],
+            transaction(&account_id, vec![Action::RemoveIdentity(identity("bob"))]),
+        );
+        assert!(
+            matches!(outcome, UserOpOutcome::Rejected(reason) if reason == "Action requires a full access identity")
+        );
+
+        assert_eq!(contract.accounts.get(&account_id).unwrap().nonce(0), 0);
+    }
+
+    #[test]
+    fn auth_callback_should_reject_a_nonce_consumed_during_verification() {
+        let (mut contract, account_id) =
+            setup_account(vec![full_access("bob"), full_access("carol")]);
+        verified(vec![true]);
+
+        // Both operations were validated against nonce 0 before either callback ran
+        auth_callback(
+            &mut contract,
+            vec![identity("bob")],
+            transaction(&account_id, vec![]),
+        );
+        let outcome = auth_callback(
+            &mut contract,
+            vec![identity("carol")],
+            transaction(&account_id, vec![]),
+        );
+
+        assert!(
+            matches!(outcome, UserOpOutcome::Rejected(reason) if reason == "Nonce already used")
+        );
+        assert_eq!(contract.accounts.get(&account_id).unwrap().nonce(0), 1);
+    }
+
+    #[test]
+    fn auth_callback_should_reject_an_account_recreated_during_verification() {
+        let (mut contract, account_id) = setup_account(vec![full_access("bob")]);
+        let transaction = transaction(&account_id, vec![Action::RemoveIdentity(identity("bob"))]);
+
+        contract.remove_account(account_id.clone());
+        contract.add_account(account_id.clone(), full_access("bob"), None);
+        verified(vec![true]);
+
+        let outcome = auth_callback(&mut contract, vec![identity("bob")], transaction);
+
+        assert!(
+            matches!(outcome, UserOpOutcome::Rejected(reason) if reason == "Account epoch mismatch")
+        );
+        assert_eq!(contract.get_nonce(account_id, 0), Some(U128(0)));
+    }
+
+    #[test]
+    fn auth_result_callback_should_refund_the_deposit_of_a_rejected_operation() {
+        let (mut contract, account_id) =
+            setup_account(vec![full_access("bob"), full_access("carol")]);
+
+        let promise = contract.auth_result_callback(
+            CONTRACT_ID.parse().unwrap(),
+            transaction(&account_id, vec![]),
+            U128(5),
+            None,
+            Ok(UserOpOutcome::rejected("Nonce already used")),
+        );
+
+        // The deposit is transferred back to the predecessor instead of funding the account
+        assert!(promise.is_some());
+        assert_eq!(contract.accounts.get(&account_id).unwrap().balance, U128(0));
+        assert_eq!(auth_failed_reason().unwrap(), "Nonce already used");
+    }
+
+    #[test]
+    fn auth_result_callback_should_refund_the_sponsor_of_a_rejected_operation() {
+        let (mut contract, account_id) =
+            setup_account(vec![full_access("bob"), full_access("carol")]);
+        let sponsorship = sponsorship(&mut contract, &account_id);
+
+        let promise = contract.auth_result_callback(
+            CONTRACT_ID.parse().unwrap(),
+            transaction(&account_id, vec![]),
+            U128(0),
+            Some(sponsorship),
+            Ok(UserOpOutcome::rejected("Authentication failed")),
+        );
+
+        let sponsor = contract
+            .sponsors
+            .get(&"sponsor.near".parse::<AccountId>().unwrap())
+            .unwrap();
+        assert!(promise.is_none());
+        assert_eq!(sponsor.balance, U128(100));
+        assert_eq!(sponsor.spent_today, U128(0));
+        assert_eq!(auth_failed_reason().unwrap(), "Authentication failed");
+    }
+
+    #[test]
+    fn auth_result_callback_should_reject_an_operation_that_failed_to_be_recorded() {
+        let (mut contract, account_id) =
+            setup_account(vec![full_access("bob"), full_access("carol")]);
+
+        let promise = contract.auth_result_callback(
+            CONTRACT_ID.parse().unwrap(),
+            transaction(&account_id, vec![]),
+            U128(5),
+            None,
+            Err(PromiseError::Failed),
+        );
+
+        assert!(promise.is_some());
+        assert_eq!(contract.accounts.get(&account_id).unwrap().balance, U128(0));
+        assert_eq!(
+            auth_failed_reason().unwrap(),
+            "Failed to record the user operation"
+        );
+    }
+
+    #[test]
+    fn auth_result_callback_should_credit_the_deposit_of_a_recorded_operation() {
+        let (mut contract, account_id) =
+            setup_account(vec![full_access("bob"), full_access("carol")]);
+
+        let promise = contract.auth_result_callback(
+            CONTRACT_ID.parse().unwrap(),
+            transaction(&account_id, vec![]),
+            U128(5),
+            None,
+            Ok(UserOpOutcome::Pending),
+        );
+
+        assert!(promise.is_none());
+        assert_eq!(contract.accounts.get(&account_id).unwrap().balance, U128(5));
+        assert!(auth_failed_reason().is_none());
+    }
+
+    #[test]
+    fn cancel_pending_operation_should_not_be_blocked_by_a_policy_set_during_the_delay() {
+        let (mut contract, account_id) =
+            setup_account(vec![full_access("attacker"), full_access("victim")]);
+        contract
+            .accounts
+            .get_mut(&account_id)
+            .unwrap()
+            .timelock_policy = Some(TimelockPolicy {
+            delay: U64(100),
+            actions: vec![ActionKind::RemoveIdentity],
+        });
+        verified(vec![true]);
+
+        // The attacker queues the removal of the victim, then requires 2 approvals for every action
+        let outcome = auth_callback(
+            &mut contract,
+            vec![identity("attacker")],
+            transaction(
+                &account_id,
+                vec![Action::RemoveIdentity(identity("victim"))],
+            ),
+        );
+        assert!(matches!(outcome, UserOpOutcome::Pending));
+
+        let mut set_policy = transaction(
+            &account_id,
+            vec![Action::SetMultisigPolicy(Some(MultisigPolicy {
+                threshold: 2,
+                actions: None,
+            }))],
+        );
+        set_policy.nonce = 1;
+        let UserOpOutcome::Approved(act_as) = auth_callback(
+            &mut contract,
+            vec![identity("attacker")],
+            set_policy.clone(),
+        ) else {
+            panic!("The multisig policy should apply at once");
+        };
+        contract.execute_actions(
+            CONTRACT_ID.parse().unwrap(),
+            act_as,
+            set_policy,
+            vec![],
+            None,
+        );
+
+        // The victim still vetoes the removal alone and without delay
+        let mut veto = transaction(&account_id, vec![Action::CancelPendingOperation(0)]);
+        veto.nonce = 2;
+        let UserOpOutcome::Approved(act_as) =
+            auth_callback(&mut contract, vec![identity("victim")], veto.clone())
+        else {
+            panic!("The veto should be approved by its signer alone");
+        };
+        contract.execute_actions(CONTRACT_ID.parse().unwrap(), act_as, veto, vec![], None);
+
+        assert!(contract.list_pending_operations(account_id).is_empty());
+    }
+
+    #[test]
+    fn auth_callback_should_reject_a_veto_acting_as_another_identity() {
+        let (mut contract, account_id) =
+            setup_account(vec![full_access("bob"), full_access("carol")]);
+        verified(vec![true]);
+
+        let outcome = contract.auth_callback(
+            vec![identity("bob")],
+            Some(identity("carol")),
+            transaction(&account_id, vec![Action::CancelPendingOperation(0)]),
+            CONTRACT_ID.parse().unwrap(),
+            false,
+        );
+
+        assert!(
+            matches!(outcome, UserOpOutcome::Rejected(reason) if reason == "A veto must act as one of its signers")
+        );
+        assert_eq!(contract.accounts.get(&account_id).unwrap().nonce(0), 0);
     }
 }
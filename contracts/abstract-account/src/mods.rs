@@ -1,2 +1,4 @@
 pub mod external_contracts;
+pub mod proxy;
 pub mod signer;
+pub mod tokens;
@@ -10,6 +10,7 @@ pub const VALIDATE_P256_SIGNATURE_GAS: Gas = Gas::from_tgas(30);
 pub const VALIDATE_ETH_SIGNATURE_GAS: Gas = Gas::from_tgas(12);
 
 #[ext_contract(webauthn_auth)]
+#[allow(dead_code)]
 pub trait WebAuthnAuth {
     fn verify_p256(
         &self,
@@ -19,16 +20,19 @@ pub trait WebAuthnAuth {
 }
 
 #[ext_contract(ethereum_auth)]
+#[allow(dead_code)]
 pub trait EthereumAuth {
     fn verify(&self, eth_data: WalletValidationData, compressed_public_key: String) -> bool;
 }
 
 #[ext_contract(solana_auth)]
+#[allow(dead_code)]
 pub trait SolanaAuth {
     fn verify(&self, solana_data: WalletValidationData, public_key: String) -> bool;
 }
 
 #[ext_contract(oidc_auth)]
+#[allow(dead_code)]
 pub trait OidcAuth {
     fn verify(&self, oidc_data: OIDCValidationData, oidc_identity: OIDCAuthenticator) -> bool;
 }
@@ -0,0 +1,27 @@
+use near_sdk::{
+    ext_contract,
+    json_types::{Base64VecU8, U128, U64},
+    AccountId, Gas, Promise,
+};
+
+pub const PROXY_INIT_GAS: Gas = Gas::from_tgas(10);
+// Gas used by the proxy itself, on top of the gas it forwards to the function call
+pub const PROXY_GAS: Gas = Gas::from_tgas(10);
+pub const SUB_ACCOUNT_CALLBACK_GAS: Gas = Gas::from_tgas(10);
+// Gas the proxy attaches to sub_account_deleted once it's deleted
+pub const SUB_ACCOUNT_DELETED_GAS: Gas = Gas::from_tgas(10);
+
+#[ext_contract(ext_proxy)]
+#[allow(dead_code)]
+pub trait ProxyAccount {
+    fn function_call(
+        &mut self,
+        receiver_id: AccountId,
+        method_name: String,
+        args: Base64VecU8,
+        deposit: U128,
+        gas: U64,
+    ) -> Promise;
+    fn transfer(&mut self, receiver_id: AccountId, amount: U128) -> Promise;
+    fn delete_account(&mut self) -> Promise;
+}
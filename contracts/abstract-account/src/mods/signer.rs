@@ -1,27 +1,138 @@
 use near_sdk::{
     borsh::{BorshDeserialize, BorshSerialize},
-    ext_contract,
+    env, ext_contract,
+    json_types::U128,
     serde::{Deserialize, Serialize},
     AccountId, Gas, PromiseOrValue,
 };
 use schemars::JsonSchema;
 
+use crate::types::signer::SignerVersion;
+
 pub const SIGN_GAS: Gas = Gas::from_tgas(50);
+pub const SIGNATURE_DEPOSIT_GAS: Gas = Gas::from_tgas(5);
+pub const LATEST_KEY_VERSION_GAS: Gas = Gas::from_tgas(5);
+pub const DERIVED_PUBLIC_KEY_GAS: Gas = Gas::from_tgas(10);
+
+// EdDSA signs the message itself, bounded by the signer to fit a Solana transaction
+const MIN_EDDSA_PAYLOAD_LEN: usize = 32;
+const MAX_EDDSA_PAYLOAD_LEN: usize = 1232;
+
+/// Signature scheme of a signer domain
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum SignatureScheme {
+    // secp256k1 ECDSA, for EVM and legacy Bitcoin keys
+    #[default]
+    Ecdsa,
+    // ed25519, for Solana, Aptos and NEAR keys
+    Eddsa,
+    // secp256k1 BIP-340 Schnorr, for Taproot keys
+    Schnorr,
+}
+
+impl SignatureScheme {
+    // Domains the signer registers its keys under, in the order the schemes were added
+    pub fn domain_id(&self) -> u64 {
+        match self {
+            SignatureScheme::Ecdsa => 0,
+            SignatureScheme::Eddsa => 1,
+            SignatureScheme::Schnorr => 2,
+        }
+    }
+
+    /// Panics if the payload length is not signable by the scheme
+    pub fn validate_payload(&self, payload: &[u8]) {
+        let valid = match self {
+            SignatureScheme::Ecdsa | SignatureScheme::Schnorr => payload.len() == 32,
+            SignatureScheme::Eddsa => {
+                (MIN_EDDSA_PAYLOAD_LEN..=MAX_EDDSA_PAYLOAD_LEN).contains(&payload.len())
+            }
+        };
+
+        if !valid {
+            env::panic_str(&format!("Invalid payload length for {:?}", self));
+        }
+    }
+}
 
+/// Payload of a Sign action. ECDSA and Schnorr payloads are 32 byte hashes, EdDSA payloads the message itself
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, BorshSerialize, BorshDeserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct SignRequest {
-    pub payload: [u8; 32],
+    pub payload: Vec<u8>,
     pub path: String,
     pub key_version: u32,
 }
 
-impl SignRequest {
-    pub fn new(payload: [u8; 32], path: String, key_version: u32) -> Self {
-        Self {
-            payload,
-            path,
-            key_version,
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(crate = "near_sdk::serde")]
+pub enum SignPayload {
+    Ecdsa(String),
+    Eddsa(String),
+    Schnorr(String),
+}
+
+/// Request sent to the signer, in the format of its version
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(crate = "near_sdk::serde", untagged)]
+pub enum SignRequestArgs {
+    V1 {
+        payload: [u8; 32],
+        path: String,
+        key_version: u32,
+    },
+    // Payloads are hex encoded and tagged with their scheme
+    V2 {
+        path: String,
+        payload_v2: SignPayload,
+        domain_id: u64,
+        key_version: u32,
+    },
+}
+
+impl SignRequestArgs {
+    pub fn new(
+        version: SignerVersion,
+        scheme: SignatureScheme,
+        payload: &[u8],
+        path: String,
+        key_version: u32,
+    ) -> Self {
+        match version {
+            SignerVersion::V1 => SignRequestArgs::V1 {
+                payload: payload
+                    .try_into()
+                    .unwrap_or_else(|_| env::panic_str("V1 signers only sign 32 byte payloads")),
+                path,
+                key_version,
+            },
+            SignerVersion::V2 => {
+                let payload = hex::encode(payload);
+
+                SignRequestArgs::V2 {
+                    path,
+                    payload_v2: match scheme {
+                        SignatureScheme::Ecdsa => SignPayload::Ecdsa(payload),
+                        SignatureScheme::Eddsa => SignPayload::Eddsa(payload),
+                        SignatureScheme::Schnorr => SignPayload::Schnorr(payload),
+                    },
+                    domain_id: scheme.domain_id(),
+                    key_version,
+                }
+            }
         }
     }
 }
@@ -40,20 +151,104 @@ pub struct SerializableScalar {
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, BorshSerialize, BorshDeserialize)]
 #[serde(crate = "near_sdk::serde")]
-pub struct SignResult {
+pub struct EcdsaSignature {
     pub big_r: SerializableAffinePoint,
     pub s: SerializableScalar,
     pub recovery_id: u8,
 }
 
+/// Signature returned by the signer, tagged with the curve it was produced on
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(crate = "near_sdk::serde", tag = "scheme")]
+pub enum SignResult {
+    Secp256k1(EcdsaSignature),
+    Ed25519 { signature: Vec<u8> },
+    Bip340 { signature: Vec<u8> },
+}
+
+impl SignResult {
+    // Signers without domains return the ECDSA signature untagged
+    pub fn from_slice(value: &[u8]) -> Option<Self> {
+        serde_json::from_slice(value)
+            .or_else(|_| serde_json::from_slice(value).map(SignResult::Secp256k1))
+            .ok()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(crate = "near_sdk::serde")]
+pub enum SignStatus {
+    Signed(SignResult),
+    // The deposit of a failed request is refunded to the sponsor, up to the fees it paid, and then to the account balance
+    Failed(String),
+}
+
+/// Outcome of a payload of a Sign action, as requested by the account
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SignResponse {
+    #[schemars(with = "String")]
+    pub signer_id: AccountId,
+    pub scheme: SignatureScheme,
+    pub payload: Vec<u8>,
+    pub path: String,
+    pub key_version: u32,
+    pub status: SignStatus,
+}
+
 #[ext_contract(ext_signer)]
+#[allow(dead_code)]
 pub trait SignerInterface {
-    fn sign(&mut self, request: SignRequest) -> PromiseOrValue<SignResult>;
+    fn sign(&mut self, request: SignRequestArgs) -> PromiseOrValue<SignResult>;
     fn public_key(&self) -> near_sdk::PublicKey;
     fn derived_public_key(
         &self,
         path: String,
         predecessor: Option<AccountId>,
+        domain_id: Option<u64>,
     ) -> near_sdk::PublicKey;
     fn latest_key_version(&self) -> u32;
+    // Deposit required by each sign request
+    fn experimental_signature_deposit(&self) -> U128;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_payload_should_accept_32_byte_hashes_for_ecdsa_and_schnorr() {
+        SignatureScheme::Ecdsa.validate_payload(&[1; 32]);
+        SignatureScheme::Schnorr.validate_payload(&[1; 32]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid payload length for Ecdsa")]
+    fn validate_payload_should_reject_ecdsa_payloads_that_are_not_hashes() {
+        SignatureScheme::Ecdsa.validate_payload(&[1; 33]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid payload length for Schnorr")]
+    fn validate_payload_should_reject_short_schnorr_payloads() {
+        SignatureScheme::Schnorr.validate_payload(&[1; 31]);
+    }
+
+    #[test]
+    fn validate_payload_should_accept_eddsa_messages_within_bounds() {
+        SignatureScheme::Eddsa.validate_payload(&[1; MIN_EDDSA_PAYLOAD_LEN]);
+        SignatureScheme::Eddsa.validate_payload(&[1; MAX_EDDSA_PAYLOAD_LEN]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid payload length for Eddsa")]
+    fn validate_payload_should_reject_eddsa_messages_below_bounds() {
+        SignatureScheme::Eddsa.validate_payload(&[1; MIN_EDDSA_PAYLOAD_LEN - 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid payload length for Eddsa")]
+    fn validate_payload_should_reject_eddsa_messages_above_bounds() {
+        SignatureScheme::Eddsa.validate_payload(&[1; MAX_EDDSA_PAYLOAD_LEN + 1]);
+    }
 }
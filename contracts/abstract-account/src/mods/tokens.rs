@@ -0,0 +1,23 @@
+use near_sdk::{ext_contract, json_types::U128, AccountId, Gas};
+
+pub const FT_TRANSFER_GAS: Gas = Gas::from_tgas(10);
+pub const NFT_TRANSFER_GAS: Gas = Gas::from_tgas(20);
+pub const CUSTODY_CALLBACK_GAS: Gas = Gas::from_tgas(10);
+
+#[ext_contract(ext_ft)]
+#[allow(dead_code)]
+pub trait FungibleToken {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+#[ext_contract(ext_nft)]
+#[allow(dead_code)]
+pub trait NonFungibleToken {
+    fn nft_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: String,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    );
+}
@@ -1,3 +1,14 @@
 pub mod account;
+pub mod account_id;
+pub mod address;
+pub mod custody;
+pub mod domain;
+pub mod event;
 pub mod identity;
+pub mod multisig;
+pub mod paymaster;
+pub mod recovery;
+pub mod signer;
+pub mod timelock;
 pub mod transaction;
+pub mod view;
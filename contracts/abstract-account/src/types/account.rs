@@ -1,20 +1,105 @@
-use super::identity::{Identity, IdentityWithPermissions};
+use super::{
+    account_id::AbstractAccountId,
+    custody::CustodyAsset,
+    identity::{Identity, IdentityWithPermissions},
+    multisig::MultisigPolicy,
+    recovery::{PendingRecovery, RecoveryConfig},
+    timelock::{PendingOperation, TimelockPolicy},
+    transaction::Action,
+};
+use interfaces::traits::path::Path;
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::U128,
+    require,
     serde::{Deserialize, Serialize},
 };
 use schemars::JsonSchema;
+use std::collections::BTreeMap;
+
+/// Selects which path the foreign chain keys of an account are derived from
+#[derive(
+    Debug,
+    BorshDeserialize,
+    BorshSerialize,
+    Deserialize,
+    Serialize,
+    JsonSchema,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Default,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum SigningPathMode {
+    // Each identity controls its own keys, signing as the act_as identity
+    #[default]
+    Identity,
+    // Every identity signs for the same account keys, act_as is not used for signing
+    Account,
+}
 
 #[derive(Debug, BorshDeserialize, BorshSerialize, Deserialize, Serialize, JsonSchema, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Account {
     pub identities: Vec<IdentityWithPermissions>,
-    pub nonce: u128,
+    // Unique per account creation, binds signatures to this incarnation of the account_id
+    pub epoch: u64,
+    // Next nonce of each nonce key, lanes that were never used start at 0
+    pub nonces: BTreeMap<u64, u128>,
+    pub multisig_policy: Option<MultisigPolicy>,
+    pub recovery_config: Option<RecoveryConfig>,
+    // One per proposed identity, so a guardian approving first can't block the recovery the others approve
+    pub pending_recoveries: Vec<PendingRecovery>,
+    // Incremented whenever a recovery is finalized, cancelled or reconfigured, invalidating outstanding guardian approvals
+    pub recovery_nonce: u128,
+    pub timelock_policy: Option<TimelockPolicy>,
+    pub pending_operations: Vec<PendingOperation>,
+    pub next_operation_id: u64,
+    // yoctoNEAR held by the account, pays for its signature fees
+    #[schemars(with = "String")]
+    pub balance: U128,
+    pub signing_path_mode: SigningPathMode,
+    // NEP-141 balances held in custody, keyed by token contract
+    #[schemars(with = "BTreeMap<String, String>")]
+    pub fungible_tokens: BTreeMap<String, U128>,
+    // NEP-171 token ids held in custody, keyed by token contract
+    pub non_fungible_tokens: BTreeMap<String, Vec<String>>,
+    // Native NEAR account running the proxy contract, set while it's being created
+    pub sub_account: Option<String>,
 }
 
 impl Account {
-    pub fn new(identities: Vec<IdentityWithPermissions>, nonce: u128) -> Self {
-        Self { identities, nonce }
+    pub fn new(identities: Vec<IdentityWithPermissions>, epoch: u64) -> Self {
+        Self {
+            identities,
+            epoch,
+            nonces: BTreeMap::new(),
+            multisig_policy: None,
+            recovery_config: None,
+            pending_recoveries: vec![],
+            recovery_nonce: 0,
+            timelock_policy: None,
+            pending_operations: vec![],
+            next_operation_id: 0,
+            balance: U128(0),
+            signing_path_mode: SigningPathMode::Identity,
+            fungible_tokens: BTreeMap::new(),
+            non_fungible_tokens: BTreeMap::new(),
+            sub_account: None,
+        }
+    }
+
+    pub fn nonce(&self, nonce_key: u64) -> u128 {
+        self.nonces.get(&nonce_key).copied().unwrap_or(0)
+    }
+
+    /// Consumes the current nonce of the lane, returning whether the lane was used for the first time
+    pub fn increment_nonce(&mut self, nonce_key: u64) -> bool {
+        let nonce = self.nonce(nonce_key);
+        self.nonces.insert(nonce_key, nonce + 1).is_none()
     }
 
     pub fn get_identity(&self, identity: &Identity) -> Option<&IdentityWithPermissions> {
@@ -30,4 +115,123 @@ impl Account {
     pub fn remove_identity(&mut self, identity: &Identity) {
         self.identities.retain(|curr| &curr.identity != identity);
     }
+
+    pub fn has_full_access(&self, identity: &Identity) -> bool {
+        self.get_identity(identity)
+            .is_some_and(|identity| identity.permissions.is_none())
+    }
+
+    pub fn full_access_identities_count(&self) -> u32 {
+        self.identities
+            .iter()
+            .filter(|curr| curr.permissions.is_none())
+            .count() as u32
+    }
+
+    /// Number of approvals required to execute the actions, 1 when no multisig policy applies to any of them
+    pub fn required_approvals(&self, actions: &[Action]) -> u32 {
+        self.multisig_policy
+            .as_ref()
+            .filter(|policy| actions.iter().any(|action| policy.applies_to(action)))
+            .map_or(1, |policy| policy.threshold)
+    }
+
+    pub fn holds_tokens(&self) -> bool {
+        !self.fungible_tokens.is_empty() || !self.non_fungible_tokens.is_empty()
+    }
+
+    pub fn credit(&mut self, asset: CustodyAsset) {
+        match asset {
+            CustodyAsset::Near(amount) => {
+                self.balance = U128(self.balance.0 + amount.0);
+            }
+            CustodyAsset::FungibleToken { token_id, amount } => {
+                let balance = self.fungible_tokens.entry(token_id).or_insert(U128(0));
+                *balance = U128(balance.0 + amount.0);
+            }
+            CustodyAsset::NonFungibleToken { token_id, nft_id } => {
+                self.non_fungible_tokens
+                    .entry(token_id)
+                    .or_default()
+                    .push(nft_id);
+            }
+        }
+    }
+
+    /// Removes every token of the token contract from custody, returning whether the account held any
+    pub fn drop_token(&mut self, token_id: &str) -> bool {
+        let fungible_tokens = self.fungible_tokens.remove(token_id).is_some();
+        let non_fungible_tokens = self.non_fungible_tokens.remove(token_id).is_some();
+
+        fungible_tokens || non_fungible_tokens
+    }
+
+    /// Panics if the account doesn't hold the asset
+    pub fn debit(&mut self, asset: &CustodyAsset) {
+        match asset {
+            CustodyAsset::Near(amount) => {
+                require!(self.balance.0 >= amount.0, "Insufficient account balance");
+                self.balance = U128(self.balance.0 - amount.0);
+            }
+            CustodyAsset::FungibleToken { token_id, amount } => {
+                let balance = self
+                    .fungible_tokens
+                    .get(token_id)
+                    .map_or(0, |balance| balance.0);
+                require!(balance >= amount.0, "Insufficient token balance");
+
+                if balance == amount.0 {
+                    self.fungible_tokens.remove(token_id);
+                } else {
+                    self.fungible_tokens
+                        .insert(token_id.clone(), U128(balance - amount.0));
+                }
+            }
+            CustodyAsset::NonFungibleToken { token_id, nft_id } => {
+                let nft_ids = self
+                    .non_fungible_tokens
+                    .get_mut(token_id)
+                    .unwrap_or_else(|| env::panic_str("Token not held by the account"));
+                let index = nft_ids
+                    .iter()
+                    .position(|curr| curr == nft_id)
+                    .unwrap_or_else(|| env::panic_str("Token not held by the account"));
+                nft_ids.remove(index);
+
+                if nft_ids.is_empty() {
+                    self.non_fungible_tokens.remove(token_id);
+                }
+            }
+        }
+    }
+
+    /// Root of the paths the signer derives the account keys from, the identity is only required in Identity mode
+    pub fn signing_root(
+        &self,
+        account_id: &AbstractAccountId,
+        identity: Option<&Identity>,
+    ) -> String {
+        match self.signing_path_mode {
+            // The epoch keeps a re-created account_id from controlling the keys of the previous account
+            SigningPathMode::Account => format!("account/{}/{}", account_id, self.epoch),
+            SigningPathMode::Identity => {
+                let identity = identity.unwrap_or_else(|| {
+                    env::panic_str("Identity signing path mode requires an identity")
+                });
+                if self.get_identity(identity).is_none() {
+                    env::panic_str("Identity not found in account");
+                }
+
+                identity.path()
+            }
+        }
+    }
+
+    /// Delay in nanoseconds the actions must wait before being executed, if the timelock policy applies to any of them
+    pub fn timelock_delay(&self, actions: &[Action]) -> Option<u64> {
+        self.timelock_policy
+            .as_ref()
+            .filter(|policy| actions.iter().any(|action| policy.applies_to(action)))
+            .map(|policy| policy.delay.0)
+    }
 }
@@ -0,0 +1,179 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    serde::{Deserialize, Serialize},
+};
+use schemars::JsonSchema;
+use std::fmt;
+
+const MIN_LENGTH: usize = 2;
+const MAX_LENGTH: usize = 64;
+
+/*
+Id of an abstract account, validated on every entry point so it can be used as a storage key and derivation path segment.
+
+Ids are normalized to lowercase, then must be 2 to 64 characters of a-z, 0-9, '-' and '_', starting and ending
+with a letter or digit. ASCII only ids can't contain Unicode confusables, and the path separators of build_account_path
+(',' and '/') and of sub-accounts ('.') can't appear in them.
+*/
+#[derive(
+    Debug,
+    BorshDeserialize,
+    BorshSerialize,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Clone,
+)]
+#[serde(crate = "near_sdk::serde", try_from = "String", into = "String")]
+#[schemars(transparent)]
+pub struct AbstractAccountId(String);
+
+impl AbstractAccountId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn validate(account_id: &str) -> Result<(), &'static str> {
+        if !(MIN_LENGTH..=MAX_LENGTH).contains(&account_id.len()) {
+            return Err("Account id must be between 2 and 64 characters");
+        }
+
+        if !account_id
+            .bytes()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == b'-' || c == b'_')
+        {
+            return Err("Account id can only contain a-z, 0-9, '-' and '_'");
+        }
+
+        let is_alphanumeric = |c: Option<u8>| c.is_some_and(|c| c.is_ascii_alphanumeric());
+        if !is_alphanumeric(account_id.bytes().next())
+            || !is_alphanumeric(account_id.bytes().next_back())
+        {
+            return Err("Account id must start and end with a letter or digit");
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for AbstractAccountId {
+    type Error = &'static str;
+
+    fn try_from(account_id: String) -> Result<Self, Self::Error> {
+        let account_id = account_id.to_ascii_lowercase();
+        Self::validate(&account_id)?;
+
+        Ok(Self(account_id))
+    }
+}
+
+impl std::str::FromStr for AbstractAccountId {
+    type Err = &'static str;
+
+    fn from_str(account_id: &str) -> Result<Self, Self::Err> {
+        Self::try_from(account_id.to_string())
+    }
+}
+
+impl From<AbstractAccountId> for String {
+    fn from(account_id: AbstractAccountId) -> Self {
+        account_id.0
+    }
+}
+
+impl fmt::Display for AbstractAccountId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for AbstractAccountId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for AbstractAccountId {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(account_id: &str) -> Result<AbstractAccountId, &'static str> {
+        account_id.parse()
+    }
+
+    #[test]
+    fn should_accept_valid_ids() {
+        for account_id in [
+            "ab",
+            "alice",
+            "alice-1",
+            "alice_bob",
+            "0x1234",
+            &"a".repeat(64),
+        ] {
+            assert_eq!(parse(account_id).unwrap().as_str(), account_id);
+        }
+    }
+
+    #[test]
+    fn should_reject_ids_out_of_length_bounds() {
+        for account_id in ["", "a", &"a".repeat(65)] {
+            assert_eq!(
+                parse(account_id),
+                Err("Account id must be between 2 and 64 characters")
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_ids_outside_the_charset() {
+        for account_id in [
+            "alice.near",
+            "alice/1",
+            "alice,1",
+            "alice bob",
+            "alicé",
+            "аlice",
+        ] {
+            assert_eq!(
+                parse(account_id),
+                Err("Account id can only contain a-z, 0-9, '-' and '_'")
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_ids_not_starting_and_ending_with_alphanumerics() {
+        for account_id in ["-alice", "alice-", "_alice", "alice_", "--"] {
+            assert_eq!(
+                parse(account_id),
+                Err("Account id must start and end with a letter or digit")
+            );
+        }
+    }
+
+    #[test]
+    fn should_normalize_ids_to_lowercase() {
+        assert_eq!(parse("Alice-BOB").unwrap().as_str(), "alice-bob");
+        assert_eq!(parse("ALICE").unwrap(), parse("alice").unwrap());
+    }
+
+    #[test]
+    fn should_validate_deserialized_ids() {
+        let account_id: AbstractAccountId = near_sdk::serde_json::from_str("\"Alice\"").unwrap();
+        assert_eq!(account_id.as_str(), "alice");
+
+        assert!(near_sdk::serde_json::from_str::<AbstractAccountId>("\"alice.near\"").is_err());
+    }
+}
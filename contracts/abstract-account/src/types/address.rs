@@ -0,0 +1,141 @@
+use near_sdk::{
+    bs58, env,
+    serde::{Deserialize, Serialize},
+    CurveType, PublicKey,
+};
+use schemars::JsonSchema;
+
+use crate::mods::signer::SignatureScheme;
+
+/// Chain whose address format a derived public key is rendered in
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub enum AddressChain {
+    // Keccak-256 based 0x address, shared by every EVM chain
+    Evm,
+    // Legacy P2PKH address, on mainnet or testnet following the contract network
+    Bitcoin,
+    // Base58 ed25519 public key
+    Solana,
+}
+
+impl AddressChain {
+    /// Scheme of the signer domain the chain keys are derived in
+    pub fn scheme(&self) -> SignatureScheme {
+        match self {
+            AddressChain::Evm | AddressChain::Bitcoin => SignatureScheme::Ecdsa,
+            AddressChain::Solana => SignatureScheme::Eddsa,
+        }
+    }
+
+    pub fn format_address(&self, public_key: &PublicKey, network_id: &str) -> String {
+        match self {
+            AddressChain::Evm => {
+                let key = secp256k1_key(public_key);
+                format!("0x{}", hex::encode(&env::keccak256(key)[12..]))
+            }
+            AddressChain::Bitcoin => {
+                let key = secp256k1_key(public_key);
+
+                // Compressed key prefix holds the parity of y
+                let mut compressed = vec![0x02 | (key[63] & 1)];
+                compressed.extend_from_slice(&key[..32]);
+
+                let version = if network_id == "mainnet" { 0x00 } else { 0x6f };
+                let mut address = vec![version];
+                address.extend_from_slice(&env::ripemd160_array(&env::sha256_array(&compressed)));
+
+                let checksum = env::sha256_array(&env::sha256_array(&address));
+                address.extend_from_slice(&checksum[..4]);
+
+                bs58::encode(address).into_string()
+            }
+            AddressChain::Solana => {
+                if public_key.curve_type() != CurveType::ED25519 {
+                    env::panic_str("Solana addresses require an ed25519 key");
+                }
+
+                bs58::encode(&public_key.as_bytes()[1..]).into_string()
+            }
+        }
+    }
+}
+
+// Uncompressed secp256k1 key without its 0x04 prefix
+fn secp256k1_key(public_key: &PublicKey) -> &[u8] {
+    if public_key.curve_type() != CurveType::SECP256K1 {
+        env::panic_str("EVM and Bitcoin addresses require a secp256k1 key");
+    }
+
+    &public_key.as_bytes()[1..]
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DerivedAddress {
+    pub path: String,
+    pub public_key: String,
+    pub address: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // secp256k1 generator point, the public key of private key 1
+    const GENERATOR: &str = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798\
+                             483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+
+    fn secp256k1(key: &str) -> PublicKey {
+        PublicKey::from_parts(CurveType::SECP256K1, hex::decode(key).unwrap()).unwrap()
+    }
+
+    fn ed25519(key: &str) -> PublicKey {
+        PublicKey::from_parts(CurveType::ED25519, hex::decode(key).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn format_address_should_render_evm_addresses() {
+        assert_eq!(
+            AddressChain::Evm.format_address(&secp256k1(GENERATOR), "testnet"),
+            "0x7e5f4552091a69125d5dfcb7b8c2659029395bdf"
+        );
+    }
+
+    #[test]
+    fn format_address_should_render_bitcoin_addresses_of_the_network() {
+        let key = secp256k1(GENERATOR);
+
+        assert_eq!(
+            AddressChain::Bitcoin.format_address(&key, "mainnet"),
+            "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH"
+        );
+        assert_eq!(
+            AddressChain::Bitcoin.format_address(&key, "testnet"),
+            "mrCDrCybB6J1vRfbwM5hemdJz73FwDBC8r"
+        );
+    }
+
+    #[test]
+    fn format_address_should_render_solana_addresses() {
+        // The system program id is the all zero key
+        assert_eq!(
+            AddressChain::Solana.format_address(&ed25519(&"00".repeat(32)), "testnet"),
+            "11111111111111111111111111111111"
+        );
+        // RFC 8032 test 1 public key
+        assert_eq!(
+            AddressChain::Solana.format_address(
+                &ed25519("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"),
+                "testnet"
+            ),
+            "FVen3X669xLzsi6N2V91DoiyzHzg1uAgqiT8jZ9nS96Z"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Solana addresses require an ed25519 key")]
+    fn format_address_should_reject_keys_of_another_curve() {
+        AddressChain::Solana.format_address(&secp256k1(GENERATOR), "testnet");
+    }
+}
@@ -0,0 +1,13 @@
+use near_sdk::{
+    json_types::U128,
+    serde::{Deserialize, Serialize},
+};
+
+/// Asset the contract holds on behalf of an account
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum CustodyAsset {
+    Near(U128),
+    FungibleToken { token_id: String, amount: U128 },
+    NonFungibleToken { token_id: String, nft_id: String },
+}
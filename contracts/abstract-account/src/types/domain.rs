@@ -0,0 +1,20 @@
+use interfaces::traits::signable_message::MessageEncoding;
+use near_sdk::{
+    borsh::{self, BorshSerialize},
+    serde::{Deserialize, Serialize},
+};
+use schemars::JsonSchema;
+
+// Bumped whenever the format of the signed messages changes, in any of their encodings
+pub const MESSAGE_VERSION: u32 = 2;
+
+/// Binds signed messages to a single deployment, so signatures can't be replayed on other contracts or networks
+#[derive(Debug, Deserialize, Serialize, JsonSchema, BorshSerialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Domain {
+    pub contract_id: String,
+    pub network_id: String,
+    pub version: u32,
+    // The encoding of the auth isn't signed, so the message commits to it
+    pub encoding: MessageEncoding,
+}
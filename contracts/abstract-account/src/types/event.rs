@@ -0,0 +1,25 @@
+use near_sdk::{json_types::U128, near};
+
+use super::account_id::AbstractAccountId;
+
+#[near(event_json(standard = "abstract_account"))]
+pub enum AbstractAccountEvent {
+    /// The user operation was rejected after its credentials were verified asynchronously, or couldn't be recorded.
+    /// Its nonce was not consumed and its deposit was refunded
+    #[event_version("1.0.0")]
+    AuthFailed {
+        account_id: AbstractAccountId,
+        nonce_key: u64,
+        nonce: U128,
+        reason: String,
+    },
+    /// The user operation was authenticated and its nonce consumed, but its actions failed and none of them was applied.
+    /// Its deposit was credited to the account
+    #[event_version("1.0.0")]
+    ExecutionFailed {
+        account_id: AbstractAccountId,
+        nonce_key: u64,
+        nonce: U128,
+        reason: String,
+    },
+}
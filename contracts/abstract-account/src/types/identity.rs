@@ -1,14 +1,19 @@
 use interfaces::{
-    auth::{oidc::OIDCAuthenticator, wallet::WalletAuthenticator, webauthn::WebAuthnAuthenticator},
-    traits::path::Path,
+    auth::{
+        oidc::OIDCAuthenticator,
+        wallet::{WalletAuthenticator, WalletType},
+        webauthn::WebAuthnAuthenticator,
+    },
+    traits::{path::Path, signable_message::MessageEncoding},
 };
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
     serde::{Deserialize, Serialize},
 };
 use schemars::JsonSchema;
 
-use super::account::Account;
+use super::{account::Account, account_id::AbstractAccountId};
 
 #[derive(
     Debug,
@@ -52,42 +57,72 @@ pub struct IdentityPermissions {
     Clone,
 )]
 #[serde(crate = "near_sdk::serde")]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Identity {
     Wallet(WalletAuthenticator),
     WebAuthn(WebAuthnAuthenticator),
     OIDC(OIDCAuthenticator),
-    Account(String),
+    Account(AbstractAccountId),
 }
 
 impl Identity {
+    /// Encoding of the signed message when the auth doesn't select one.
+    /// WebAuthn challenges are limited in size, so they carry the digest of the message
+    pub fn default_message_encoding(&self) -> MessageEncoding {
+        match self {
+            Identity::WebAuthn(_) => MessageEncoding::Sha256,
+            _ => MessageEncoding::CanonicalJson,
+        }
+    }
+
+    pub fn auth_type(&self) -> AuthTypeNames {
+        match self {
+            Identity::Wallet(wallet) => match wallet.wallet_type {
+                WalletType::Ethereum => AuthTypeNames::EthereumWallet,
+                WalletType::Solana => AuthTypeNames::SolanaWallet,
+            },
+            Identity::WebAuthn(_) => AuthTypeNames::WebAuthn,
+            Identity::OIDC(_) => AuthTypeNames::OIDC,
+            Identity::Account(_) => AuthTypeNames::Account,
+        }
+    }
+
+    /// Key of the identity in the identity to accounts index. WebAuthn identities without a public key
+    /// match any public key of the same key_id, so the public key is left out of the key
+    pub fn index_key(&self) -> [u8; 32] {
+        let mut identity = self.clone();
+        if let Identity::WebAuthn(ref mut webauthn) = identity {
+            webauthn.compressed_public_key = None;
+        }
+
+        env::sha256_array(&borsh::to_vec(&identity).expect("Failed to serialize identity"))
+    }
+
     /// Injects the compressed public key into a WebAuthn identity from an account's stored identity
     /// If the identity is not WebAuthn, does nothing
     pub fn inject_webauthn_compressed_public_key(&mut self, account: &Account) {
-        match self {
-            Identity::WebAuthn(webauthn) => {
-                let webauthn_authenticator = account
-                    .identities
-                    .iter()
-                    .find_map(|identity_with_permissions| {
-                        if let Identity::WebAuthn(ref current_webauthn) =
-                            identity_with_permissions.identity
-                        {
-                            if current_webauthn.key_id == webauthn.key_id {
-                                return Some(current_webauthn);
-                            }
+        if let Identity::WebAuthn(webauthn) = self {
+            let webauthn_authenticator = account
+                .identities
+                .iter()
+                .find_map(|identity_with_permissions| {
+                    if let Identity::WebAuthn(ref current_webauthn) =
+                        identity_with_permissions.identity
+                    {
+                        if current_webauthn.key_id == webauthn.key_id {
+                            return Some(current_webauthn);
                         }
-                        None
-                    })
-                    .expect("WebAuthn Key ID not found on Account");
+                    }
+                    None
+                })
+                .expect("WebAuthn Key ID not found on Account");
 
-                let compressed_public_key = webauthn_authenticator
-                    .compressed_public_key
-                    .as_ref()
-                    .expect("WebAuthnAuthenticator does not have a compressed public key");
+            let compressed_public_key = webauthn_authenticator
+                .compressed_public_key
+                .as_ref()
+                .expect("WebAuthnAuthenticator does not have a compressed public key");
 
-                webauthn.compressed_public_key = Some(compressed_public_key.to_string());
-            }
-            _ => {}
+            webauthn.compressed_public_key = Some(compressed_public_key.to_string());
         }
     }
 }
@@ -116,7 +151,7 @@ impl Path for Identity {
             Identity::Wallet(wallet) => wallet.path(),
             Identity::WebAuthn(webauthn) => webauthn.path(),
             Identity::OIDC(oidc) => oidc.path(),
-            Identity::Account(account) => format!("{}", account),
+            Identity::Account(account) => account.to_string(),
         }
     }
 }
@@ -137,6 +172,7 @@ impl Path for Identity {
     Hash,
 )]
 #[serde(crate = "near_sdk::serde")]
+#[allow(clippy::upper_case_acronyms)]
 pub enum AuthTypeNames {
     EthereumWallet,
     SolanaWallet,
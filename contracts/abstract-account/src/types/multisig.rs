@@ -0,0 +1,164 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    serde::{Deserialize, Serialize},
+};
+use schemars::JsonSchema;
+
+use super::{
+    identity::Identity,
+    transaction::{Action, ActionKind, Transaction},
+};
+
+#[derive(
+    Debug,
+    BorshDeserialize,
+    BorshSerialize,
+    Deserialize,
+    Serialize,
+    JsonSchema,
+    PartialEq,
+    Eq,
+    Clone,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MultisigPolicy {
+    /// Number of distinct full access identities that must approve a transaction (M of N)
+    pub threshold: u32,
+    /// Action classes that require the threshold. None means every action requires it.
+    /// Changing the policy, adding identities and setting the guardians always require it,
+    /// so a single identity can't lower the threshold or add full access identities it controls.
+    /// Vetoing a pending operation or cancelling a recovery never requires it, so a policy set during the delay
+    /// can't block them
+    pub actions: Option<Vec<ActionKind>>,
+}
+
+impl MultisigPolicy {
+    pub fn applies_to(&self, action: &Action) -> bool {
+        if matches!(
+            action,
+            Action::CancelPendingOperation(_) | Action::CancelRecovery
+        ) {
+            return false;
+        }
+
+        matches!(
+            action,
+            Action::SetMultisigPolicy(_)
+                | Action::AddIdentity(_)
+                | Action::AddIdentityWithAuth(_)
+                | Action::SetRecoveryConfig(_)
+        ) || self
+            .actions
+            .as_ref()
+            .is_none_or(|actions| actions.contains(&action.kind()))
+    }
+}
+
+// Time a proposal can gather approvals for, in nanoseconds (7 days)
+pub const PROPOSAL_TTL: u64 = 7 * 86_400_000_000_000;
+
+/// A transaction waiting for enough approvals to be executed, keyed by the transaction hash
+#[derive(Deserialize, Serialize, JsonSchema, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Proposal {
+    /*
+    act_as is not part of the signed transaction, so every approval must agree on it.
+    Otherwise an approver could redirect a Sign action to a different identity path.
+    */
+    pub act_as: Option<Identity>,
+    pub transaction: Transaction,
+    pub approvals: Vec<Identity>,
+    // Block timestamp after which the proposal can no longer be approved, in nanoseconds
+    pub expires_at: u64,
+}
+
+impl Proposal {
+    pub fn new(act_as: Option<Identity>, transaction: Transaction) -> Self {
+        Self {
+            act_as,
+            transaction,
+            approvals: vec![],
+            expires_at: env::block_timestamp() + PROPOSAL_TTL,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        env::block_timestamp() > self.expires_at
+    }
+
+    pub fn is_approved_by(&self, identity: &Identity) -> bool {
+        self.approvals.iter().any(|approval| approval == identity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::identity::IdentityWithPermissions;
+
+    fn policy(actions: Option<Vec<ActionKind>>) -> MultisigPolicy {
+        MultisigPolicy {
+            threshold: 2,
+            actions,
+        }
+    }
+
+    #[test]
+    fn applies_to_should_match_every_action_without_action_classes() {
+        let policy = policy(None);
+
+        assert!(policy.applies_to(&Action::RemoveAccount));
+        assert!(policy.applies_to(&Action::CancelProposal("hash".to_string())));
+    }
+
+    #[test]
+    fn applies_to_should_match_only_listed_action_classes() {
+        let policy = policy(Some(vec![ActionKind::RemoveAccount]));
+
+        assert!(policy.applies_to(&Action::RemoveAccount));
+        assert!(!policy.applies_to(&Action::CancelProposal("hash".to_string())));
+    }
+
+    #[test]
+    fn applies_to_should_always_match_identity_and_guardian_changes() {
+        let policy = policy(Some(vec![
+            ActionKind::RemoveIdentity,
+            ActionKind::RemoveAccount,
+        ]));
+
+        assert!(
+            policy.applies_to(&Action::AddIdentity(IdentityWithPermissions {
+                identity: Identity::Account("eve".parse().unwrap()),
+                permissions: None,
+            }))
+        );
+        assert!(policy.applies_to(&Action::SetRecoveryConfig(None)));
+    }
+
+    #[test]
+    fn applies_to_should_never_match_cancel_pending_operation() {
+        assert!(!policy(None).applies_to(&Action::CancelPendingOperation(0)));
+        assert!(!policy(Some(vec![ActionKind::CancelPendingOperation]))
+            .applies_to(&Action::CancelPendingOperation(0)));
+    }
+
+    #[test]
+    fn applies_to_should_never_match_cancel_recovery() {
+        assert!(!policy(None).applies_to(&Action::CancelRecovery));
+        assert!(!policy(Some(vec![ActionKind::CancelRecovery])).applies_to(&Action::CancelRecovery));
+    }
+
+    #[test]
+    fn applies_to_should_always_match_set_multisig_policy() {
+        let policy = policy(Some(vec![]));
+
+        assert!(policy.applies_to(&Action::SetMultisigPolicy(None)));
+        assert!(
+            policy.applies_to(&Action::SetMultisigPolicy(Some(MultisigPolicy {
+                threshold: 1,
+                actions: None,
+            })))
+        );
+    }
+}
@@ -0,0 +1,318 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    json_types::{U128, U64},
+    require,
+    serde::{Deserialize, Serialize},
+    AccountId,
+};
+use schemars::JsonSchema;
+
+use super::{
+    account_id::AbstractAccountId,
+    transaction::{ActionKind, Transaction},
+};
+
+const NANOS_PER_DAY: u64 = 86_400_000_000_000;
+
+// Relayers are reimbursed at the minimum NEAR gas price, in yoctoNEAR per gas unit
+pub const GAS_PRICE: u128 = 100_000_000;
+
+// Gas reimbursed per action when the policy doesn't set it
+pub const DEFAULT_GAS_PER_ACTION: u64 = 10_000_000_000_000;
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Deserialize, Serialize, JsonSchema, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SponsorPolicy {
+    // Accounts whose user operations are sponsored, a sponsor never pays for accounts it doesn't know
+    pub accounts: Vec<AbstractAccountId>,
+    // Actions that are sponsored, None sponsors every action
+    pub actions: Option<Vec<ActionKind>>,
+    // Maximum yoctoNEAR spent per day
+    #[schemars(with = "String")]
+    pub daily_cap: U128,
+    /*
+    Gas reimbursed per action of the user operation, DEFAULT_GAS_PER_ACTION when None.
+
+    The cost is fixed so it doesn't depend on the prepaid gas: the protocol refunds the unused prepaid gas
+    to the relayer, so reimbursing it would let relayers farm the sponsor with cheap operations.
+    It should be set to the gas the sponsored actions burn.
+    */
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub gas_per_action: Option<U64>,
+    /*
+    Signature fees paid at most per user operation, none when unset.
+
+    The relayer chooses the sponsor and the max_fee of auth_sponsored, which the account doesn't sign,
+    so the sponsor caps what a single operation can take from its balance.
+    */
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub max_fee_per_op: U128,
+}
+
+impl SponsorPolicy {
+    /// Gas reimbursed to the relayer of a user operation, never more than the gas it prepaid
+    pub fn reimbursed_gas(&self, transaction: &Transaction, prepaid_gas: u64) -> u64 {
+        let gas_per_action = self
+            .gas_per_action
+            .map_or(DEFAULT_GAS_PER_ACTION, |gas| gas.0);
+
+        prepaid_gas.min(gas_per_action.saturating_mul(transaction.actions.len() as u64))
+    }
+
+    /// Signature fees the sponsor pays at most for a user operation, requested by the relayer
+    pub fn max_fee(&self, requested: Option<U128>) -> U128 {
+        let max_fee = requested.unwrap_or(U128(0));
+        require!(
+            max_fee.0 <= self.max_fee_per_op.0,
+            "Max fee exceeds the sponsor policy"
+        );
+
+        max_fee
+    }
+
+    pub fn allows(&self, transaction: &Transaction) -> bool {
+        self.accounts.contains(&transaction.account_id)
+            && self.actions.as_ref().is_none_or(|actions| {
+                transaction
+                    .actions
+                    .iter()
+                    .all(|action| actions.contains(&action.kind()))
+            })
+    }
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Deserialize, Serialize, JsonSchema, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Sponsor {
+    #[schemars(with = "String")]
+    pub balance: U128,
+    pub policy: SponsorPolicy,
+    // Days since the unix epoch of the last sponsored user operation
+    pub day: u64,
+    #[schemars(with = "String")]
+    pub spent_today: U128,
+}
+
+impl Sponsor {
+    pub fn new(policy: SponsorPolicy) -> Self {
+        Self {
+            balance: U128(0),
+            policy,
+            day: 0,
+            spent_today: U128(0),
+        }
+    }
+
+    /// Debits the cost from the balance, enforcing the daily cap
+    pub fn charge(&mut self, cost: u128) {
+        let today = env::block_timestamp() / NANOS_PER_DAY;
+        if self.day != today {
+            self.day = today;
+            self.spent_today = U128(0);
+        }
+
+        let spent_today = self.spent_today.0 + cost;
+
+        require!(self.balance.0 >= cost, "Insufficient sponsor balance");
+        require!(
+            spent_today <= self.policy.daily_cap.0,
+            "Sponsor daily cap exceeded"
+        );
+
+        self.balance = U128(self.balance.0 - cost);
+        self.spent_today = U128(spent_today);
+    }
+
+    /// Credits back a cost charged for a user operation that failed authentication
+    pub fn refund(&mut self, cost: u128, day: u64) {
+        self.balance = U128(self.balance.0 + cost);
+
+        if self.day == day {
+            self.spent_today = U128(self.spent_today.0.saturating_sub(cost));
+        }
+    }
+}
+
+/// Sponsor of a user operation. Its gas fee is reserved from the sponsor balance while the operation is authenticated
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Sponsorship {
+    pub sponsor_id: AccountId,
+    pub relayer: AccountId,
+    // Day the gas fee was charged on
+    pub day: u64,
+    pub gas_fee: U128,
+    // Signature fees the sponsor pays at most, charged when the Sign actions are dispatched
+    pub max_fee: U128,
+}
+
+/// Signature fees charged to a sponsor, refunded to it first when the signature requests fail
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SponsoredFee {
+    pub sponsor_id: AccountId,
+    // Day the fees were charged on
+    pub day: u64,
+    pub amount: U128,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::transaction::Action;
+    use crate::utils::test_utils::{context, transaction};
+    use near_sdk::testing_env;
+
+    fn policy(accounts: Vec<AbstractAccountId>) -> SponsorPolicy {
+        SponsorPolicy {
+            accounts,
+            actions: None,
+            daily_cap: U128(u128::MAX),
+            gas_per_action: None,
+            max_fee_per_op: U128(0),
+        }
+    }
+
+    fn sponsor(balance: u128, daily_cap: u128) -> Sponsor {
+        let mut sponsor = Sponsor::new(SponsorPolicy {
+            daily_cap: U128(daily_cap),
+            ..policy(vec![])
+        });
+        sponsor.balance = U128(balance);
+
+        sponsor
+    }
+
+    fn at_day(day: u64) {
+        testing_env!(context().block_timestamp(day * NANOS_PER_DAY).build());
+    }
+
+    #[test]
+    fn charge_should_debit_the_balance_and_track_the_daily_spending() {
+        at_day(3);
+        let mut sponsor = sponsor(100, 50);
+
+        sponsor.charge(30);
+
+        assert_eq!(sponsor.balance, U128(70));
+        assert_eq!(sponsor.day, 3);
+        assert_eq!(sponsor.spent_today, U128(30));
+    }
+
+    #[test]
+    #[should_panic(expected = "Sponsor daily cap exceeded")]
+    fn charge_should_enforce_the_daily_cap() {
+        at_day(3);
+        let mut sponsor = sponsor(100, 50);
+
+        sponsor.charge(30);
+        sponsor.charge(30);
+    }
+
+    #[test]
+    fn charge_should_reset_the_daily_spending_on_a_new_day() {
+        at_day(3);
+        let mut sponsor = sponsor(100, 50);
+        sponsor.charge(30);
+
+        at_day(4);
+        sponsor.charge(30);
+
+        assert_eq!(sponsor.balance, U128(40));
+        assert_eq!(sponsor.day, 4);
+        assert_eq!(sponsor.spent_today, U128(30));
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient sponsor balance")]
+    fn charge_should_fail_without_balance() {
+        at_day(3);
+        let mut sponsor = sponsor(10, u128::MAX);
+
+        sponsor.charge(30);
+    }
+
+    #[test]
+    fn refund_should_only_restore_the_spending_of_the_same_day() {
+        at_day(3);
+        let mut sponsor = sponsor(100, 50);
+        sponsor.charge(30);
+
+        sponsor.refund(10, 3);
+        assert_eq!(sponsor.balance, U128(80));
+        assert_eq!(sponsor.spent_today, U128(20));
+
+        // A refund of a previous day is credited without freeing today's cap
+        sponsor.refund(10, 2);
+        assert_eq!(sponsor.balance, U128(90));
+        assert_eq!(sponsor.spent_today, U128(20));
+    }
+
+    #[test]
+    fn allows_should_match_the_policy_accounts_and_actions() {
+        let alice: AbstractAccountId = "alice".parse().unwrap();
+        let bob = "bob".parse().unwrap();
+        let policy = SponsorPolicy {
+            actions: Some(vec![ActionKind::RemoveAccount]),
+            ..policy(vec![alice.clone()])
+        };
+
+        assert!(self::policy(vec![alice.clone()])
+            .allows(&transaction(&alice, vec![Action::CancelRecovery])));
+        assert!(!self::policy(vec![alice]).allows(&transaction(&bob, vec![Action::CancelRecovery])));
+        assert!(policy.allows(&transaction(
+            &"alice".parse().unwrap(),
+            vec![Action::RemoveAccount]
+        )));
+        assert!(!policy.allows(&transaction(&bob, vec![Action::RemoveAccount])));
+        assert!(!policy.allows(&transaction(
+            &"alice".parse().unwrap(),
+            vec![Action::RemoveAccount, Action::CancelRecovery]
+        )));
+    }
+
+    #[test]
+    fn reimbursed_gas_should_be_a_fixed_cost_per_action() {
+        let account_id = "alice".parse().unwrap();
+        let policy = SponsorPolicy {
+            gas_per_action: Some(U64(10)),
+            ..policy(vec![])
+        };
+        let transaction = transaction(
+            &account_id,
+            vec![Action::CancelRecovery, Action::RemoveAccount],
+        );
+
+        assert_eq!(policy.reimbursed_gas(&transaction, u64::MAX), 20);
+        assert_eq!(policy.reimbursed_gas(&transaction, 5), 5);
+        assert_eq!(
+            self::policy(vec![]).reimbursed_gas(&transaction, u64::MAX),
+            2 * DEFAULT_GAS_PER_ACTION
+        );
+    }
+
+    #[test]
+    fn max_fee_should_default_to_nothing_sponsored() {
+        let policy = SponsorPolicy {
+            max_fee_per_op: U128(10),
+            ..policy(vec![])
+        };
+
+        assert_eq!(policy.max_fee(None), U128(0));
+        assert_eq!(policy.max_fee(Some(U128(10))), U128(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "Max fee exceeds the sponsor policy")]
+    fn max_fee_should_be_capped_by_the_policy() {
+        let policy = SponsorPolicy {
+            max_fee_per_op: U128(10),
+            ..policy(vec![])
+        };
+
+        policy.max_fee(Some(U128(11)));
+    }
+}
@@ -0,0 +1,100 @@
+use interfaces::traits::signable_message::{MessageKind, SignableMessage};
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    json_types::U64,
+    serde::{Deserialize, Serialize},
+};
+use schemars::JsonSchema;
+use std::collections::BTreeMap;
+
+use super::{account_id::AbstractAccountId, domain::Domain, identity::Identity};
+
+// Longest delay a recovery config can set, in nanoseconds (30 days)
+pub const MAX_RECOVERY_DELAY: u64 = 30 * 86_400_000_000_000;
+
+#[derive(
+    Debug,
+    BorshDeserialize,
+    BorshSerialize,
+    Deserialize,
+    Serialize,
+    JsonSchema,
+    PartialEq,
+    Eq,
+    Clone,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RecoveryConfig {
+    /*
+    Guardians are identities that don't control the account, but can jointly add a new full access identity to it.
+    Identity::Account guardians approve through their own abstract account with Action::ApproveRecovery,
+    every other identity type signs the RecoveryApproval message and calls approve_recovery.
+    */
+    pub guardians: Vec<Identity>,
+    pub threshold: u32,
+    /// Nanoseconds between the threshold being reached and the new identity being added
+    #[schemars(with = "String")]
+    pub delay: U64,
+    /// Epochs of the Identity::Account guardians, recorded by the contract when the config is set.
+    /// A guardian account removed and recreated by someone else has another epoch, so it can't approve
+    #[serde(default, skip_deserializing)]
+    pub guardian_epochs: BTreeMap<AbstractAccountId, u64>,
+}
+
+#[derive(
+    Debug,
+    BorshDeserialize,
+    BorshSerialize,
+    Deserialize,
+    Serialize,
+    JsonSchema,
+    PartialEq,
+    Eq,
+    Clone,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingRecovery {
+    pub identity: Identity,
+    pub approvals: Vec<Identity>,
+    /// Block timestamp after which the recovery can be finalized, set once the threshold is reached
+    #[schemars(with = "Option<String>")]
+    pub executable_at: Option<U64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RecoveryApproval {
+    pub account_id: AbstractAccountId,
+    // Must match the account epoch, so approvals can't be replayed on a recreated account
+    pub epoch: u64,
+    // The new full access identity, WebAuthn identities must include the compressed public key
+    pub identity: Identity,
+    // Must match the account recovery nonce, so approvals can't be replayed after a recovery is finalized or cancelled
+    pub nonce: u128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct RecoveryApprovalSignableMessage<'a> {
+    domain: &'a Domain,
+    #[serde(flatten)]
+    approval: &'a RecoveryApproval,
+}
+
+impl SignableMessage for RecoveryApproval {
+    type Context<'a> = &'a Domain;
+
+    const KIND: MessageKind = MessageKind::RecoveryApproval;
+
+    fn to_signed_message(&self, domain: Self::Context<'_>) -> String {
+        serde_json_canonicalizer::to_string(&RecoveryApprovalSignableMessage {
+            domain,
+            approval: self,
+        })
+        .expect("Failed to canonicalize recovery approval")
+    }
+
+    fn to_borsh_message(&self, domain: Self::Context<'_>) -> Vec<u8> {
+        borsh::to_vec(&(domain, self)).expect("Failed to serialize recovery approval")
+    }
+}
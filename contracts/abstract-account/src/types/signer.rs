@@ -0,0 +1,67 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    serde::{Deserialize, Serialize},
+    AccountId, NearToken,
+};
+use schemars::JsonSchema;
+
+use crate::mods::signer::{SignRequest, SignatureScheme};
+
+/// Request format understood by a signer contract
+#[derive(
+    Debug,
+    BorshDeserialize,
+    BorshSerialize,
+    Deserialize,
+    Serialize,
+    JsonSchema,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum SignerVersion {
+    // ECDSA only signers, taking the 32 byte payload and key version
+    V1,
+    // Signers with a domain per signature scheme, taking hex encoded payloads
+    V2,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Deserialize, Serialize, JsonSchema, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SignerConfig {
+    pub version: SignerVersion,
+    pub schemes: Vec<SignatureScheme>,
+}
+
+impl Default for SignerConfig {
+    fn default() -> Self {
+        Self {
+            version: SignerVersion::V2,
+            schemes: vec![SignatureScheme::Ecdsa],
+        }
+    }
+}
+
+/// Fee and latest key version fetched from a signer before its requests are dispatched
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SignerQuote {
+    pub signer_id: AccountId,
+    pub deposit: NearToken,
+    pub latest_key_version: u32,
+}
+
+/// A payload sent to a signer, with the deposit refunded to the account if the request fails
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SignCall {
+    pub signer_id: AccountId,
+    pub version: SignerVersion,
+    pub scheme: SignatureScheme,
+    // Root of the request path, see Account::signing_root, resolved when the Sign action was reached
+    pub signing_root: String,
+    pub request: SignRequest,
+    pub deposit: NearToken,
+}
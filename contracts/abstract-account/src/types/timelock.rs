@@ -0,0 +1,109 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    json_types::U64,
+    serde::{Deserialize, Serialize},
+};
+use schemars::JsonSchema;
+
+use super::{
+    identity::Identity,
+    transaction::{Action, ActionKind, Transaction},
+};
+
+// Longest delay a timelock policy can set, in nanoseconds (30 days)
+pub const MAX_TIMELOCK_DELAY: u64 = 30 * 86_400_000_000_000;
+
+#[derive(
+    Debug,
+    BorshDeserialize,
+    BorshSerialize,
+    Deserialize,
+    Serialize,
+    JsonSchema,
+    PartialEq,
+    Eq,
+    Clone,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TimelockPolicy {
+    /// Nanoseconds a sensitive action waits in the pending queue before it can be executed
+    #[schemars(with = "String")]
+    pub delay: U64,
+    /// Action classes that are queued instead of being executed immediately. Vetoes and recovery cancellations
+    /// can't be listed
+    pub actions: Vec<ActionKind>,
+}
+
+impl TimelockPolicy {
+    pub fn applies_to(&self, action: &Action) -> bool {
+        // A delayed veto or cancellation would only apply once the operation or the recovery was executed
+        if matches!(
+            action,
+            Action::CancelPendingOperation(_) | Action::CancelRecovery
+        ) {
+            return false;
+        }
+
+        // Changing the policy is always delayed, otherwise a stolen key could remove it before acting
+        matches!(action, Action::SetTimelockPolicy(_)) || self.actions.contains(&action.kind())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingOperation {
+    pub id: u64,
+    pub act_as: Identity,
+    pub transaction: Transaction,
+    // Identities that authorized the transaction
+    pub requested_by: Vec<Identity>,
+    #[schemars(with = "String")]
+    pub executable_at: U64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(actions: Vec<ActionKind>) -> TimelockPolicy {
+        TimelockPolicy {
+            delay: U64(1),
+            actions,
+        }
+    }
+
+    #[test]
+    fn applies_to_should_match_only_listed_action_classes() {
+        let policy = policy(vec![ActionKind::RemoveAccount]);
+
+        assert!(policy.applies_to(&Action::RemoveAccount));
+        assert!(!policy.applies_to(&Action::CancelPendingOperation(0)));
+    }
+
+    #[test]
+    fn applies_to_should_never_match_cancel_pending_operation() {
+        let policy = policy(vec![ActionKind::CancelPendingOperation]);
+
+        assert!(!policy.applies_to(&Action::CancelPendingOperation(0)));
+    }
+
+    #[test]
+    fn applies_to_should_never_match_cancel_recovery() {
+        let policy = policy(vec![ActionKind::CancelRecovery]);
+
+        assert!(!policy.applies_to(&Action::CancelRecovery));
+    }
+
+    #[test]
+    fn applies_to_should_always_match_set_timelock_policy() {
+        let policy = policy(vec![]);
+
+        assert!(policy.applies_to(&Action::SetTimelockPolicy(None)));
+        assert!(
+            policy.applies_to(&Action::SetTimelockPolicy(Some(TimelockPolicy {
+                delay: U64(0),
+                actions: vec![],
+            })))
+        );
+    }
+}
@@ -1,8 +1,16 @@
-use crate::mods::signer::SignRequest;
+use crate::mods::signer::{SignRequest, SignatureScheme};
+use crate::types::account::SigningPathMode;
+use crate::types::account_id::AbstractAccountId;
+use crate::types::domain::Domain;
 use crate::types::identity::IdentityWithPermissions;
-use interfaces::traits::signable_message::SignableMessage;
+use crate::types::multisig::MultisigPolicy;
+use crate::types::recovery::{RecoveryApproval, RecoveryConfig};
+use crate::types::timelock::TimelockPolicy;
+use interfaces::traits::signable_message::{MessageEncoding, MessageKind, SignableMessage};
 use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
     env,
+    json_types::{U128, U64},
     serde::{Deserialize, Serialize},
 };
 use schemars::JsonSchema;
@@ -10,55 +18,234 @@ use serde_json::Value;
 
 use super::identity::{Identity, IdentityPermissions};
 
-#[derive(Deserialize, Serialize, JsonSchema, Clone)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct UserOp {
-    // Credentials must contain the signature of the transaction message in canonical JSON format.
+    // Credentials must contain the signature of the transaction message, including the contract domain, in canonical JSON format.
     // The message is canonicalized to ensure consistent signatures across different platforms.
-    pub auth: Auth,
+    pub auth: UserOpAuth,
+    // Defaults to the first auth identity
     pub act_as: Option<Identity>,
     pub transaction: Transaction,
 }
 
-#[derive(Deserialize, Serialize, JsonSchema, Clone)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Auth {
     pub identity: Identity,
     pub credentials: Value,
+    // Encoding of the signed message, defaults to the one of the identity type
+    #[serde(default)]
+    pub encoding: Option<MessageEncoding>,
 }
 
-#[derive(Deserialize, Serialize, JsonSchema, Clone)]
+/// Either a single auth or several co-signers authorizing the same transaction.
+/// Co-signers that coordinate off-chain can reach a multisig threshold in a single UserOp.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+#[serde(crate = "near_sdk::serde", untagged)]
+pub enum UserOpAuth {
+    Single(Auth),
+    Batch(Vec<Auth>),
+}
+
+impl UserOpAuth {
+    pub fn auths(&self) -> Vec<&Auth> {
+        match self {
+            UserOpAuth::Single(auth) => vec![auth],
+            UserOpAuth::Batch(auths) => auths.iter().collect(),
+        }
+    }
+
+    pub fn into_auths(self) -> Vec<Auth> {
+        match self {
+            UserOpAuth::Single(auth) => vec![auth],
+            UserOpAuth::Batch(auths) => auths,
+        }
+    }
+}
+
+/// What auth_callback did with an authenticated user operation
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum UserOpOutcome {
+    // Nothing was recorded, its nonce wasn't consumed
+    Rejected(String),
+    // Recorded as a proposal waiting for approvals or as a timelocked operation
+    Pending,
+    // Ready to be executed as the identity
+    Approved(Identity),
+}
+
+impl UserOpOutcome {
+    pub fn rejected(reason: &str) -> Self {
+        UserOpOutcome::Rejected(reason.to_string())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, BorshSerialize, BorshDeserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Transaction {
     // account_id must be signed by the Identity to bind the signature to the given account.
     // Otherwise, a signature from an Identity with permissions on multiple accounts could be used to create a transaction valid across any of them.
-    pub account_id: String,
-    // nonce must be signed to prevent replay attacks
+    pub account_id: AbstractAccountId,
+    // epoch must be signed so the signature is only valid for the current incarnation of the account_id
+    pub epoch: u64,
+    // Selects the nonce lane, so independent sessions can submit transactions concurrently
+    pub nonce_key: u64,
+    // nonce must be signed to prevent replay attacks, it's the next sequence of the nonce_key lane
     pub nonce: u128,
-    pub action: Action,
+    // Deadline after which the signed transaction can no longer be submitted, omitted from the signed message when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<ValidUntil>,
+    // Executed in order and atomically, if any action fails none of them is applied
+    pub actions: Vec<Action>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, BorshSerialize, BorshDeserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ValidUntil {
+    // Block timestamp in nanoseconds
+    Timestamp(#[schemars(with = "String")] U64),
+    BlockHeight(#[schemars(with = "String")] U64),
+}
+
+impl ValidUntil {
+    pub fn is_expired(&self) -> bool {
+        match self {
+            ValidUntil::Timestamp(timestamp) => env::block_timestamp() > timestamp.0,
+            ValidUntil::BlockHeight(height) => env::block_height() > height.0,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct TransactionSignableMessage<'a> {
+    domain: &'a Domain,
+    #[serde(flatten)]
+    transaction: &'a Transaction,
 }
 
 impl SignableMessage for Transaction {
-    type Context<'a> = ();
+    type Context<'a> = &'a Domain;
+
+    const KIND: MessageKind = MessageKind::Transaction;
+
+    fn to_signed_message(&self, domain: Self::Context<'_>) -> String {
+        serde_json_canonicalizer::to_string(&TransactionSignableMessage {
+            domain,
+            transaction: self,
+        })
+        .expect("Failed to canonicalize transaction")
+    }
+
+    fn to_borsh_message(&self, domain: Self::Context<'_>) -> Vec<u8> {
+        borsh::to_vec(&(domain, self)).expect("Failed to serialize transaction")
+    }
+}
+
+impl Transaction {
+    /// Identifies the transaction by the hash of its canonical JSON, so every identity approving it signs the same payload
+    pub fn hash(&self) -> String {
+        let transaction =
+            serde_json_canonicalizer::to_string(&self).expect("Failed to canonicalize transaction");
+
+        hex::encode(env::sha256(transaction.as_bytes()))
+    }
+
+    pub fn requires_full_access(&self) -> bool {
+        self.actions.iter().any(Action::requires_full_access)
+    }
+
+    pub fn cancels_pending_operation(&self) -> bool {
+        self.actions
+            .iter()
+            .any(|action| matches!(action, Action::CancelPendingOperation(_)))
+    }
 
-    fn to_signed_message(&self, _: ()) -> String {
-        serde_json_canonicalizer::to_string(&self).expect("Failed to canonicalize transaction")
+    pub fn removes_account(&self) -> bool {
+        self.actions
+            .iter()
+            .any(|action| matches!(action, Action::RemoveAccount))
     }
 }
 
-#[derive(Deserialize, Serialize, JsonSchema, Clone)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, BorshSerialize, BorshDeserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct AddIdentityWithAuth {
     pub identity_with_permissions: IdentityWithPermissions,
+    #[borsh(
+        serialize_with = "crate::utils::utils::borsh_serialize_json",
+        deserialize_with = "crate::utils::utils::borsh_deserialize_json"
+    )]
     pub credentials: Value,
+    // Encoding of the message signed by the identity being added, defaults to the one of its identity type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<MessageEncoding>,
 }
 
-#[derive(Deserialize, Serialize, JsonSchema, Clone)]
+/// Proof that the account creator possesses the identity the account is created with
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CreationAuth {
+    // Credentials must contain the signature of the AccountCreation message
+    pub credentials: Value,
+    // Encoding of the signed message, defaults to the one of the identity type
+    #[serde(default)]
+    pub encoding: Option<MessageEncoding>,
+}
+
+/// Message signed by the identity an account is created with. It has no nonce: replaying it can only
+/// recreate the same account_id controlled by the same identity
+pub struct AccountCreation<'a> {
+    pub account_id: &'a AbstractAccountId,
+    pub identity_with_permissions: &'a IdentityWithPermissions,
+}
+
+#[derive(Serialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+struct AccountCreationSignableMessage<'a> {
+    domain: &'a Domain,
+    account_id: &'a AbstractAccountId,
+    // Tells the creation message apart from the action messages signed by identities
+    action: &'static str,
+    identity: &'a Identity,
+    permissions: &'a Option<IdentityPermissions>,
+}
+
+impl AccountCreation<'_> {
+    fn signable_message<'a>(&'a self, domain: &'a Domain) -> AccountCreationSignableMessage<'a> {
+        AccountCreationSignableMessage {
+            domain,
+            account_id: self.account_id,
+            action: "AddAccount",
+            identity: &self.identity_with_permissions.identity,
+            permissions: &self.identity_with_permissions.permissions,
+        }
+    }
+}
+
+impl SignableMessage for AccountCreation<'_> {
+    type Context<'a> = &'a Domain;
+
+    const KIND: MessageKind = MessageKind::AccountCreation;
+
+    fn to_signed_message(&self, domain: Self::Context<'_>) -> String {
+        serde_json_canonicalizer::to_string(&self.signable_message(domain))
+            .expect("Failed to canonicalize account creation")
+    }
+
+    fn to_borsh_message(&self, domain: Self::Context<'_>) -> Vec<u8> {
+        borsh::to_vec(&self.signable_message(domain)).expect("Failed to serialize account creation")
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, BorshSerialize, BorshDeserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub enum Action {
     RemoveAccount,
     /*
-    Credentials must contain the signature of account_id, nonce, action, permissions to:
+    Credentials must contain the signature of domain, account_id, epoch, nonce_key, nonce, action, permissions to:
     1. Prove ownership of the IdentityWithPermissions
     2. Declare which account it intends to be added to
     3. Prevent replay attacks
@@ -68,39 +255,345 @@ pub enum Action {
     AddIdentity(IdentityWithPermissions),
     RemoveIdentity(Identity),
     Sign(SignPayloadsRequest),
+    // None removes the policy, so any single identity can authorize transactions again
+    SetMultisigPolicy(Option<MultisigPolicy>),
+    // Drops a pending proposal, identified by its transaction hash, without executing it
+    CancelProposal(String),
+    // None disables recovery. Requires a full access identity
+    SetRecoveryConfig(Option<RecoveryConfig>),
+    // Cancels the pending recoveries during their delay, whatever the multisig and timelock policies.
+    // Requires a full access identity
+    CancelRecovery,
+    // Approves the recovery of another account that has this account as guardian
+    ApproveRecovery(RecoveryApproval),
+    // None removes the policy. The change itself waits for the current policy delay. Requires a full access identity
+    SetTimelockPolicy(Option<TimelockPolicy>),
+    // Vetoes a queued operation during its delay, whatever the multisig and timelock policies.
+    // Requires acting as a full access identity that signed the transaction and didn't request the operation
+    CancelPendingOperation(u64),
+    // Transfers NEAR from the account balance. Requires a full access identity
+    Withdraw {
+        receiver_id: String,
+        #[schemars(with = "String")]
+        amount: U128,
+    },
+    // Switches the keys the account signs for. Requires a full access identity
+    SetSigningPathMode(SigningPathMode),
+    // Transfers NEP-141 tokens held in custody for the account. Requires a full access identity
+    FtTransfer {
+        token_id: String,
+        receiver_id: String,
+        #[schemars(with = "String")]
+        amount: U128,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        memo: Option<String>,
+    },
+    // Transfers a NEP-171 token held in custody for the account. Requires a full access identity
+    NftTransfer {
+        token_id: String,
+        nft_id: String,
+        receiver_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        memo: Option<String>,
+    },
+    /*
+    Gives up the tokens of the token contract held in custody, e.g. tokens sent by a contract that fails every transfer,
+    so they no longer block RemoveAccount. Requires a full access identity
+    */
+    DropToken {
+        token_id: String,
+    },
+    /*
+    Creates the account_id sub-account of the contract and deploys the proxy contract to it, funded from the account balance.
+    The initial balance must cover the storage of the proxy contract. Requires a full access identity.
+    Contracts are only called through the sub-account: anything a call from the shared contract opened, e.g. wrapped NEAR
+    or a staking position, would belong to the contract and could be taken by any other account
+    */
+    CreateSubAccount {
+        #[schemars(with = "String")]
+        initial_balance: U128,
+    },
+    // Calls a contract as the sub-account, attaching a deposit from the sub-account balance. Requires a full access identity
+    SubAccountFunctionCall {
+        receiver_id: String,
+        method_name: String,
+        #[borsh(
+            serialize_with = "crate::utils::utils::borsh_serialize_json",
+            deserialize_with = "crate::utils::utils::borsh_deserialize_json"
+        )]
+        args: Value,
+        #[schemars(with = "String")]
+        deposit: U128,
+        #[schemars(with = "String")]
+        gas: U64,
+    },
+    // Transfers NEAR from the sub-account balance. Requires a full access identity
+    SubAccountTransfer {
+        receiver_id: String,
+        #[schemars(with = "String")]
+        amount: U128,
+    },
+    // Deletes the sub-account, crediting its balance to the account. Requires a full access identity
+    DeleteSubAccount,
 }
 
-#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+/// Action classes used by account policies to select which actions they apply to
+#[derive(
+    Debug,
+    BorshDeserialize,
+    BorshSerialize,
+    Deserialize,
+    Serialize,
+    JsonSchema,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ActionKind {
+    RemoveAccount,
+    AddIdentityWithAuth,
+    AddIdentity,
+    RemoveIdentity,
+    Sign,
+    SetMultisigPolicy,
+    CancelProposal,
+    SetRecoveryConfig,
+    CancelRecovery,
+    ApproveRecovery,
+    SetTimelockPolicy,
+    CancelPendingOperation,
+    Withdraw,
+    SetSigningPathMode,
+    FtTransfer,
+    NftTransfer,
+    DropToken,
+    CreateSubAccount,
+    SubAccountFunctionCall,
+    SubAccountTransfer,
+    DeleteSubAccount,
+}
+
+impl Action {
+    pub fn kind(&self) -> ActionKind {
+        match self {
+            Action::RemoveAccount => ActionKind::RemoveAccount,
+            Action::AddIdentityWithAuth(_) => ActionKind::AddIdentityWithAuth,
+            Action::AddIdentity(_) => ActionKind::AddIdentity,
+            Action::RemoveIdentity(_) => ActionKind::RemoveIdentity,
+            Action::Sign(_) => ActionKind::Sign,
+            Action::SetMultisigPolicy(_) => ActionKind::SetMultisigPolicy,
+            Action::CancelProposal(_) => ActionKind::CancelProposal,
+            Action::SetRecoveryConfig(_) => ActionKind::SetRecoveryConfig,
+            Action::CancelRecovery => ActionKind::CancelRecovery,
+            Action::ApproveRecovery(_) => ActionKind::ApproveRecovery,
+            Action::SetTimelockPolicy(_) => ActionKind::SetTimelockPolicy,
+            Action::CancelPendingOperation(_) => ActionKind::CancelPendingOperation,
+            Action::Withdraw { .. } => ActionKind::Withdraw,
+            Action::SetSigningPathMode(_) => ActionKind::SetSigningPathMode,
+            Action::FtTransfer { .. } => ActionKind::FtTransfer,
+            Action::NftTransfer { .. } => ActionKind::NftTransfer,
+            Action::DropToken { .. } => ActionKind::DropToken,
+            Action::CreateSubAccount { .. } => ActionKind::CreateSubAccount,
+            Action::SubAccountFunctionCall { .. } => ActionKind::SubAccountFunctionCall,
+            Action::SubAccountTransfer { .. } => ActionKind::SubAccountTransfer,
+            Action::DeleteSubAccount => ActionKind::DeleteSubAccount,
+        }
+    }
+
+    /// Actions that identities with restricted permissions can't authorize
+    pub fn requires_full_access(&self) -> bool {
+        matches!(
+            self,
+            Action::RemoveAccount
+                | Action::AddIdentityWithAuth(_)
+                | Action::AddIdentity(_)
+                | Action::RemoveIdentity(_)
+                | Action::SetMultisigPolicy(_)
+                | Action::CancelProposal(_)
+                | Action::SetRecoveryConfig(_)
+                | Action::CancelRecovery
+                | Action::ApproveRecovery(_)
+                | Action::SetTimelockPolicy(_)
+                | Action::CancelPendingOperation(_)
+                | Action::Withdraw { .. }
+                | Action::SetSigningPathMode(_)
+                | Action::FtTransfer { .. }
+                | Action::NftTransfer { .. }
+                | Action::DropToken { .. }
+                | Action::CreateSubAccount { .. }
+                | Action::SubAccountFunctionCall { .. }
+                | Action::SubAccountTransfer { .. }
+                | Action::DeleteSubAccount
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, BorshSerialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct ActionSignableMessage {
-    pub account_id: String,
+    pub domain: Domain,
+    pub account_id: AbstractAccountId,
+    pub epoch: String,
+    pub nonce_key: String,
     pub nonce: String,
     pub action: String,
     pub permissions: Option<IdentityPermissions>,
 }
 
 impl SignableMessage for Action {
-    type Context<'a> = (&'a str, u128);
+    // The domain and the transaction containing the action
+    type Context<'a> = (&'a Domain, &'a Transaction);
+
+    const KIND: MessageKind = MessageKind::Action;
+
+    fn to_signed_message(&self, context: Self::Context<'_>) -> String {
+        serde_json_canonicalizer::to_string(&self.signable_message(context))
+            .expect("Failed to canonicalize action")
+    }
 
-    fn to_signed_message(&self, (account_id, nonce): Self::Context<'_>) -> String {
+    fn to_borsh_message(&self, context: Self::Context<'_>) -> Vec<u8> {
+        borsh::to_vec(&self.signable_message(context)).expect("Failed to serialize action")
+    }
+}
+
+impl Action {
+    fn signable_message(
+        &self,
+        (domain, transaction): (&Domain, &Transaction),
+    ) -> ActionSignableMessage {
         match self {
-            Action::AddIdentityWithAuth(auth) => {
-                serde_json_canonicalizer::to_string(&ActionSignableMessage {
-                    account_id: account_id.to_string(),
-                    nonce: nonce.to_string(),
-                    action: "AddIdentityWithAuth".to_string(),
-                    permissions: auth.identity_with_permissions.permissions.clone(),
-                })
-                .expect("Failed to canonicalize action")
-            }
+            Action::AddIdentityWithAuth(auth) => ActionSignableMessage {
+                domain: domain.clone(),
+                account_id: transaction.account_id.clone(),
+                epoch: transaction.epoch.to_string(),
+                nonce_key: transaction.nonce_key.to_string(),
+                nonce: transaction.nonce.to_string(),
+                action: "AddIdentityWithAuth".to_string(),
+                permissions: auth.identity_with_permissions.permissions.clone(),
+            },
             _ => env::panic_str("to_signed_message not supported"),
         }
     }
 }
 
-#[derive(Deserialize, Serialize, JsonSchema, Clone)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, BorshSerialize, BorshDeserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct SignPayloadsRequest {
-    pub contract_id: String,
+    // Registered signer the payloads are sent to, the default signer when omitted. Omitted from the signed message when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract_id: Option<String>,
+    // Signer domain the payloads are signed with, ECDSA when omitted. Omitted from the signed message when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<SignatureScheme>,
     pub payloads: Vec<SignRequest>,
 }
+
+impl SignPayloadsRequest {
+    pub fn scheme(&self) -> SignatureScheme {
+        self.scheme.unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::{full_access, transaction};
+    use serde_json::json;
+
+    fn domain(network_id: &str) -> Domain {
+        Domain {
+            contract_id: "abstract.near".to_string(),
+            network_id: network_id.to_string(),
+            version: 2,
+            encoding: MessageEncoding::CanonicalJson,
+        }
+    }
+
+    fn add_identity_with_auth() -> Action {
+        Action::AddIdentityWithAuth(AddIdentityWithAuth {
+            identity_with_permissions: full_access("bob"),
+            credentials: json!({}),
+            encoding: None,
+        })
+    }
+
+    #[test]
+    fn transaction_message_should_include_the_domain() {
+        let transaction = transaction(&"alice".parse().unwrap(), vec![Action::RemoveAccount]);
+
+        let message: Value =
+            serde_json::from_str(&transaction.to_signed_message(&domain("testnet"))).unwrap();
+
+        // The transaction fields are flattened next to the domain
+        assert_eq!(
+            message["domain"],
+            json!({
+                "contract_id": "abstract.near",
+                "network_id": "testnet",
+                "version": 2,
+                "encoding": "CanonicalJson"
+            })
+        );
+        assert_eq!(message["account_id"], "alice");
+        assert_eq!(message["epoch"], 1);
+    }
+
+    #[test]
+    fn transaction_message_should_differ_across_domains() {
+        let transaction = transaction(&"alice".parse().unwrap(), vec![Action::RemoveAccount]);
+
+        for encoding in [
+            MessageEncoding::CanonicalJson,
+            MessageEncoding::Borsh,
+            MessageEncoding::Sha256,
+        ] {
+            assert_ne!(
+                transaction
+                    .encode_message(&domain("testnet"), encoding)
+                    .bytes,
+                transaction
+                    .encode_message(&domain("mainnet"), encoding)
+                    .bytes
+            );
+        }
+    }
+
+    #[test]
+    fn transaction_message_should_differ_across_encodings() {
+        let transaction = transaction(&"alice".parse().unwrap(), vec![Action::RemoveAccount]);
+        let sha256 = Domain {
+            encoding: MessageEncoding::Sha256,
+            ..domain("testnet")
+        };
+
+        assert_ne!(
+            transaction.to_signed_message(&domain("testnet")),
+            transaction.to_signed_message(&sha256)
+        );
+        assert_ne!(
+            transaction.to_borsh_message(&domain("testnet")),
+            transaction.to_borsh_message(&sha256)
+        );
+    }
+
+    #[test]
+    fn action_message_should_include_the_domain_and_the_transaction() {
+        let action = add_identity_with_auth();
+        let transaction = transaction(&"alice".parse().unwrap(), vec![action.clone()]);
+        let testnet = domain("testnet");
+
+        let message: Value =
+            serde_json::from_str(&action.to_signed_message((&testnet, &transaction))).unwrap();
+
+        assert_eq!(message["domain"]["network_id"], "testnet");
+        assert_eq!(message["account_id"], "alice");
+        assert_eq!(message["epoch"], "1");
+        assert_eq!(message["action"], "AddIdentityWithAuth");
+        assert_ne!(
+            action.to_borsh_message((&testnet, &transaction)),
+            action.to_borsh_message((&domain("mainnet"), &transaction))
+        );
+    }
+}
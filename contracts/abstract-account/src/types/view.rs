@@ -0,0 +1,130 @@
+use near_sdk::serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+use super::identity::{AuthTypeNames, IdentityWithPermissions};
+
+// Entries returned by a paginated view when the limit is omitted
+pub const DEFAULT_PAGE_LIMIT: u32 = 50;
+
+/// Entries read from a cursor, pass next_cursor to the next call to continue the iteration
+#[derive(Deserialize, Serialize, JsonSchema)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    // None once every entry was read
+    pub next_cursor: Option<u32>,
+}
+
+/// Selects identities by type and permissions, every field left unset matches any identity
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct IdentityFilter {
+    pub auth_type: Option<AuthTypeNames>,
+    // Full access identities when true, limited ones when false
+    pub full_access: Option<bool>,
+    // Identities the account can act as, full access identities always can
+    pub enable_act_as: Option<bool>,
+}
+
+impl IdentityFilter {
+    pub fn matches(&self, identity_with_permissions: &IdentityWithPermissions) -> bool {
+        let permissions = identity_with_permissions.permissions.as_ref();
+
+        self.auth_type
+            .as_ref()
+            .is_none_or(|auth_type| identity_with_permissions.identity.auth_type() == *auth_type)
+            && self
+                .full_access
+                .is_none_or(|full_access| permissions.is_none() == full_access)
+            && self.enable_act_as.is_none_or(|enable_act_as| {
+                permissions.is_none_or(|permissions| permissions.enable_act_as) == enable_act_as
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::identity::{Identity, IdentityPermissions};
+    use crate::utils::test_utils::full_access;
+    use interfaces::auth::wallet::{WalletAuthenticator, WalletType};
+
+    fn limited(enable_act_as: bool) -> IdentityWithPermissions {
+        IdentityWithPermissions {
+            permissions: Some(IdentityPermissions { enable_act_as }),
+            ..full_access("bob")
+        }
+    }
+
+    fn wallet() -> IdentityWithPermissions {
+        IdentityWithPermissions {
+            identity: Identity::Wallet(WalletAuthenticator {
+                wallet_type: WalletType::Ethereum,
+                public_key: "0x04".to_string(),
+            }),
+            permissions: None,
+        }
+    }
+
+    #[test]
+    fn matches_should_match_every_identity_without_criteria() {
+        let filter = IdentityFilter::default();
+
+        assert!(filter.matches(&full_access("bob")));
+        assert!(filter.matches(&limited(false)));
+        assert!(filter.matches(&wallet()));
+    }
+
+    #[test]
+    fn matches_should_filter_by_auth_type() {
+        let filter = IdentityFilter {
+            auth_type: Some(AuthTypeNames::EthereumWallet),
+            ..Default::default()
+        };
+
+        assert!(filter.matches(&wallet()));
+        assert!(!filter.matches(&full_access("bob")));
+    }
+
+    #[test]
+    fn matches_should_filter_by_full_access() {
+        let full_access_filter = IdentityFilter {
+            full_access: Some(true),
+            ..Default::default()
+        };
+        let limited_filter = IdentityFilter {
+            full_access: Some(false),
+            ..Default::default()
+        };
+
+        assert!(full_access_filter.matches(&full_access("bob")));
+        assert!(!full_access_filter.matches(&limited(true)));
+        assert!(limited_filter.matches(&limited(true)));
+        assert!(!limited_filter.matches(&full_access("bob")));
+    }
+
+    #[test]
+    fn matches_should_treat_full_access_identities_as_enabled_for_act_as() {
+        let filter = IdentityFilter {
+            enable_act_as: Some(true),
+            ..Default::default()
+        };
+
+        assert!(filter.matches(&full_access("bob")));
+        assert!(filter.matches(&limited(true)));
+        assert!(!filter.matches(&limited(false)));
+    }
+
+    #[test]
+    fn matches_should_combine_criteria() {
+        let filter = IdentityFilter {
+            auth_type: Some(AuthTypeNames::Account),
+            full_access: Some(false),
+            enable_act_as: Some(false),
+        };
+
+        assert!(filter.matches(&limited(false)));
+        assert!(!filter.matches(&limited(true)));
+        assert!(!filter.matches(&full_access("bob")));
+    }
+}
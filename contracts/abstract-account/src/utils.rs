@@ -1 +1,4 @@
+#[cfg(test)]
+pub mod test_utils;
+#[allow(clippy::module_inception)]
 pub mod utils;
@@ -0,0 +1,104 @@
+use near_sdk::{
+    test_utils::VMContextBuilder, test_vm_config, testing_env, AccountId, NearToken, PromiseResult,
+    RuntimeFeesConfig,
+};
+use near_sdk_contract_tools::nft::nep145::Nep145Controller;
+
+use crate::*;
+
+pub const CONTRACT_ID: &str = "abstract.near";
+pub const SIGNER_ID: &str = "signer.near";
+
+pub fn context() -> VMContextBuilder {
+    let mut context = VMContextBuilder::new();
+    context
+        .current_account_id(CONTRACT_ID.parse().unwrap())
+        .predecessor_account_id(CONTRACT_ID.parse().unwrap())
+        .account_balance(NearToken::from_near(100));
+
+    context
+}
+
+pub fn setup() -> AbstractAccountContract {
+    testing_env!(context().build());
+
+    AbstractAccountContract::new(vec![], SIGNER_ID.to_string(), "testnet".to_string())
+}
+
+// Results of the promises the callback under test was chained to, in join order
+pub fn set_promise_results(results: Vec<PromiseResult>) {
+    testing_env!(
+        context().build(),
+        test_vm_config(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        results
+    );
+}
+
+pub fn register_storage(contract: &mut AbstractAccountContract, account_id: &str) {
+    let account_id: AccountId = account_id.parse().unwrap();
+
+    contract
+        .deposit_to_storage_account(&account_id, NearToken::from_near(1))
+        .unwrap();
+}
+
+/// Creates the alice account with the first identity through add_account, then adds the others,
+/// so the account has an epoch and its identities are indexed as if they were added by transactions
+pub fn setup_account(
+    identities: Vec<IdentityWithPermissions>,
+) -> (AbstractAccountContract, AbstractAccountId) {
+    let mut contract = setup();
+    register_storage(&mut contract, CONTRACT_ID);
+
+    let account_id: AbstractAccountId = "alice".parse().unwrap();
+    let mut identities = identities.into_iter();
+    contract.add_account(
+        account_id.clone(),
+        identities
+            .next()
+            .expect("At least one identity is required"),
+        None,
+    );
+    for identity_with_permissions in identities {
+        contract.add_identity(account_id.clone(), identity_with_permissions, Ok(true));
+    }
+
+    (contract, account_id)
+}
+
+pub fn identity(name: &str) -> Identity {
+    Identity::Account(name.parse().unwrap())
+}
+
+pub fn full_access(name: &str) -> IdentityWithPermissions {
+    IdentityWithPermissions {
+        identity: identity(name),
+        permissions: None,
+    }
+}
+
+pub fn insert_account(
+    contract: &mut AbstractAccountContract,
+    account_id: &str,
+    identities: Vec<IdentityWithPermissions>,
+) -> AbstractAccountId {
+    let account_id: AbstractAccountId = account_id.parse().unwrap();
+    contract
+        .accounts
+        .insert(account_id.clone(), Account::new(identities, 1));
+
+    account_id
+}
+
+pub fn transaction(account_id: &AbstractAccountId, actions: Vec<Action>) -> Transaction {
+    Transaction {
+        account_id: account_id.clone(),
+        epoch: 1,
+        nonce_key: 0,
+        nonce: 0,
+        valid_until: None,
+        actions,
+    }
+}
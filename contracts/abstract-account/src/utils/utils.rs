@@ -1,4 +1,4 @@
-use near_sdk::serde;
+use near_sdk::{borsh, env, json_types::U128, serde, NearToken, PromiseResult};
 
 pub fn build_account_path(account_id: String, path: String) -> String {
     format!("{},{}", account_id, path)
@@ -10,3 +10,47 @@ where
 {
     serde_json::from_str(&credentials_json.to_string()).expect("Invalid credentials data")
 }
+
+// serde_json::Value has no Borsh implementation, so it's stored as its JSON string
+pub fn borsh_serialize_json<W: borsh::io::Write>(
+    value: &serde_json::Value,
+    writer: &mut W,
+) -> Result<(), borsh::io::Error> {
+    borsh::BorshSerialize::serialize(&value.to_string(), writer)
+}
+
+pub fn borsh_deserialize_json<R: borsh::io::Read>(
+    reader: &mut R,
+) -> Result<serde_json::Value, borsh::io::Error> {
+    let json: String = borsh::BorshDeserialize::deserialize_reader(reader)?;
+    serde_json::from_str(&json)
+        .map_err(|e| borsh::io::Error::new(borsh::io::ErrorKind::InvalidData, e))
+}
+
+/// Reads the promise result at the given index as a verifier response, treating failures as false
+pub fn promise_result_as_bool(index: u64) -> bool {
+    match env::promise_result(index) {
+        PromiseResult::Successful(value) => serde_json::from_slice(&value).unwrap_or(false),
+        PromiseResult::Failed => false,
+    }
+}
+
+/// Reads the promise result at the given index as a yoctoNEAR amount, panicking if the promise failed
+pub fn promise_result_as_yocto(index: u64) -> NearToken {
+    match env::promise_result(index) {
+        PromiseResult::Successful(value) => NearToken::from_yoctonear(
+            serde_json::from_slice::<U128>(&value)
+                .expect("Invalid amount")
+                .0,
+        ),
+        PromiseResult::Failed => env::panic_str("Failed to fetch amount"),
+    }
+}
+
+/// Reads the promise result at the given index as a u32, panicking if the promise failed
+pub fn promise_result_as_u32(index: u64) -> u32 {
+    match env::promise_result(index) {
+        PromiseResult::Successful(value) => serde_json::from_slice(&value).expect("Invalid number"),
+        PromiseResult::Failed => env::panic_str("Failed to fetch number"),
+    }
+}
@@ -1,4 +1,3 @@
-use hex;
 use interfaces::auth::wallet::WalletValidationData;
 use k256::ecdsa::{RecoveryId, Signature as K256Signature, VerifyingKey};
 use near_sdk::{log, near, require};
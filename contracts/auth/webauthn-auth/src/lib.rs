@@ -1,4 +1,3 @@
-use hex;
 use interfaces::auth::webauthn::WebAuthnValidationData;
 use near_sdk::{log, near};
 use p256::{
@@ -54,10 +53,8 @@ impl WebAuthnAuthContract {
 
         let key = P256PublicKey::from_sec1_bytes(&compressed_bytes)
             .map_err(|_| String::from("Invalid SEC1 encoding in public key"))?;
-        Ok(
-            VerifyingKey::from_encoded_point(&key.to_encoded_point(false))
-                .map_err(|_| String::from("Failed to create verifying key from point"))?,
-        )
+        VerifyingKey::from_encoded_point(&key.to_encoded_point(false))
+            .map_err(|_| String::from("Failed to create verifying key from point"))
     }
 
     #[inline(always)]
@@ -84,12 +81,12 @@ impl WebAuthnAuthContract {
     }
 
     #[inline(always)]
-    fn create_signature(&self, signature: &String) -> Result<P256Signature, String> {
+    fn create_signature(&self, signature: &str) -> Result<P256Signature, String> {
         let sig_bytes = hex::decode(signature.strip_prefix("0x").unwrap_or(signature))
             .map_err(|_| "Invalid hex encoding in signature")?;
 
-        Ok(P256Signature::try_from(sig_bytes.as_slice())
-            .map_err(|_| String::from("Invalid signature format"))?)
+        P256Signature::try_from(sig_bytes.as_slice())
+            .map_err(|_| String::from("Invalid signature format"))
     }
 }
 
@@ -1,6 +1,122 @@
+use near_sdk::{
+    borsh::{BorshDeserialize, BorshSerialize},
+    env,
+    serde::{Deserialize, Serialize},
+};
+use schemars::JsonSchema;
+
+/// Encoding of the message an identity signs
+#[derive(
+    Debug,
+    BorshDeserialize,
+    BorshSerialize,
+    Deserialize,
+    Serialize,
+    JsonSchema,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum MessageEncoding {
+    // Human readable canonical JSON
+    CanonicalJson,
+    // Compact Borsh bytes
+    Borsh,
+    // SHA-256 digest of the canonical JSON, for signers with small screens or limited memory
+    Sha256,
+}
+
+/// Kind of a signed message, the first byte of its Borsh encoding.
+/// Borsh messages aren't self describing, so without it the bytes of a message of one kind could decode as another
+#[derive(BorshSerialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[borsh(use_discriminant = true)]
+#[repr(u8)]
+pub enum MessageKind {
+    Transaction = 0,
+    RecoveryApproval = 1,
+    Action = 2,
+    AccountCreation = 3,
+}
+
+/// Message bytes in the encoding selected by the signer
+pub struct EncodedMessage {
+    pub encoding: MessageEncoding,
+    pub bytes: Vec<u8>,
+}
+
+impl EncodedMessage {
+    /// Text signed by wallets and OIDC providers: the JSON itself, or the hex encoded bytes
+    pub fn to_text(&self) -> String {
+        match self.encoding {
+            MessageEncoding::CanonicalJson => {
+                String::from_utf8(self.bytes.clone()).expect("Invalid canonical JSON message")
+            }
+            MessageEncoding::Borsh | MessageEncoding::Sha256 => hex::encode(&self.bytes),
+        }
+    }
+}
+
 // Raw message allows direct signature verification by wallets and other auth methods
 pub trait SignableMessage {
-    type Context<'a>;
+    type Context<'a>: Copy;
+
+    const KIND: MessageKind;
 
     fn to_signed_message(&self, context: Self::Context<'_>) -> String;
+
+    fn to_borsh_message(&self, context: Self::Context<'_>) -> Vec<u8>;
+
+    fn encode_message(
+        &self,
+        context: Self::Context<'_>,
+        encoding: MessageEncoding,
+    ) -> EncodedMessage {
+        let bytes = match encoding {
+            MessageEncoding::CanonicalJson => self.to_signed_message(context).into_bytes(),
+            MessageEncoding::Borsh => {
+                [vec![Self::KIND as u8], self.to_borsh_message(context)].concat()
+            }
+            MessageEncoding::Sha256 => env::sha256(self.to_signed_message(context).as_bytes()),
+        };
+
+        EncodedMessage { encoding, bytes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Message;
+
+    impl SignableMessage for Message {
+        type Context<'a> = ();
+
+        const KIND: MessageKind = MessageKind::RecoveryApproval;
+
+        fn to_signed_message(&self, _: Self::Context<'_>) -> String {
+            "{}".to_string()
+        }
+
+        fn to_borsh_message(&self, _: Self::Context<'_>) -> Vec<u8> {
+            vec![7, 8]
+        }
+    }
+
+    #[test]
+    fn encode_message_should_prefix_borsh_messages_with_their_kind() {
+        let message = Message.encode_message((), MessageEncoding::Borsh);
+
+        assert_eq!(message.bytes, vec![1, 7, 8]);
+        assert_eq!(message.to_text(), "010708");
+    }
+
+    #[test]
+    fn encode_message_should_not_prefix_json_messages() {
+        let message = Message.encode_message((), MessageEncoding::CanonicalJson);
+
+        assert_eq!(message.bytes, b"{}".to_vec());
+    }
 }
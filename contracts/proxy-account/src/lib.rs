@@ -0,0 +1,159 @@
+use near_sdk::{
+    env, ext_contract,
+    json_types::{Base64VecU8, U128, U64},
+    near, require, AccountId, Gas, NearToken, PanicOnDefault, Promise,
+};
+
+const SUB_ACCOUNT_DELETED_GAS: Gas = Gas::from_tgas(10);
+
+#[ext_contract(ext_owner)]
+#[allow(dead_code)]
+trait Owner {
+    fn sub_account_deleted(&mut self, balance: U128);
+}
+
+/// Native NEAR account of an abstract account, deployed as a sub-account of the abstract-account contract.
+/// Only the owner contract can act through it, once the abstract account authorized the action
+#[near(contract_state)]
+#[derive(PanicOnDefault)]
+pub struct ProxyAccountContract {
+    owner: AccountId,
+}
+
+#[near]
+impl ProxyAccountContract {
+    #[init]
+    pub fn new(owner: AccountId) -> Self {
+        Self { owner }
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner.clone()
+    }
+
+    /// Calls a contract as this account, attaching a deposit from its balance
+    pub fn function_call(
+        &mut self,
+        receiver_id: AccountId,
+        method_name: String,
+        args: Base64VecU8,
+        deposit: U128,
+        gas: U64,
+    ) -> Promise {
+        self.assert_owner();
+
+        Promise::new(receiver_id).function_call(
+            method_name,
+            args.into(),
+            NearToken::from_yoctonear(deposit.0),
+            Gas::from_gas(gas.0),
+        )
+    }
+
+    pub fn transfer(&mut self, receiver_id: AccountId, amount: U128) -> Promise {
+        self.assert_owner();
+
+        Promise::new(receiver_id).transfer(NearToken::from_yoctonear(amount.0))
+    }
+
+    /// Deletes this account, sending its whole balance to the owner, then tells the owner the balance it received.
+    /// The balance is read before the deletion: only the owner moves it, and the gas reward of this call only adds to it
+    pub fn delete_account(&mut self) -> Promise {
+        self.assert_owner();
+
+        let balance = U128(env::account_balance().as_yoctonear());
+
+        Promise::new(env::current_account_id())
+            .delete_account(self.owner.clone())
+            .then(
+                ext_owner::ext(self.owner.clone())
+                    .with_static_gas(SUB_ACCOUNT_DELETED_GAS)
+                    .sub_account_deleted(balance),
+            )
+    }
+
+    fn assert_owner(&self) {
+        require!(
+            env::predecessor_account_id() == self.owner,
+            "Only the owner can act through the proxy"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::{
+        mock::MockAction,
+        test_utils::{get_created_receipts, VMContextBuilder},
+        testing_env,
+    };
+
+    fn owner() -> AccountId {
+        "abstract.near".parse().unwrap()
+    }
+
+    fn setup(predecessor: AccountId) -> ProxyAccountContract {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id("alice.abstract.near".parse().unwrap())
+            .predecessor_account_id(predecessor)
+            .build());
+
+        ProxyAccountContract::new(owner())
+    }
+
+    #[test]
+    fn owner_should_transfer() {
+        let mut contract = setup(owner());
+
+        drop(contract.transfer("bob.near".parse().unwrap(), U128(1)));
+
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(
+            receipts[0].receiver_id,
+            "bob.near".parse::<AccountId>().unwrap()
+        );
+        assert!(matches!(
+            receipts[0].actions.as_slice(),
+            [MockAction::Transfer { deposit, .. }] if *deposit == NearToken::from_yoctonear(1)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can act through the proxy")]
+    fn function_call_should_fail_for_other_accounts() {
+        let mut contract = setup("bob.near".parse().unwrap());
+
+        contract.function_call(
+            "token.near".parse().unwrap(),
+            "ft_transfer".to_string(),
+            Base64VecU8::from(b"{}".to_vec()),
+            U128(1),
+            U64(10_000_000_000_000),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can act through the proxy")]
+    fn delete_account_should_fail_for_other_accounts() {
+        let mut contract = setup("bob.near".parse().unwrap());
+
+        contract.delete_account();
+    }
+
+    #[test]
+    fn delete_account_should_send_the_balance_to_the_owner() {
+        let mut contract = setup(owner());
+
+        drop(contract.delete_account());
+
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 2);
+        assert!(matches!(
+            receipts[0].actions.as_slice(),
+            [MockAction::DeleteAccount { beneficiary_id, .. }] if *beneficiary_id == owner()
+        ));
+        assert_eq!(receipts[1].receiver_id, owner());
+    }
+}
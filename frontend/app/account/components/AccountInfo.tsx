@@ -4,6 +4,7 @@ import { Account } from "chainsig-aa.js";
 import React from "react";
 
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { nextNonce } from "@/lib/utils";
 
 interface AccountInfoProps {
   account: Account;
@@ -24,9 +25,13 @@ const AccountInfo: React.FC<AccountInfoProps> = ({ account, accountId }) => {
             <span className="text-sm font-medium">Account ID</span>
             <span className="font-mono text-xs">{accountId}</span>
           </div>
+          <div className="flex items-center justify-between">
+            <span className="text-sm font-medium">Epoch</span>
+            <span className="font-mono text-xs">{account.epoch}</span>
+          </div>
           <div className="flex items-center justify-between">
             <span className="text-sm font-medium">Nonce</span>
-            <span className="font-mono text-xs">{account.nonce}</span>
+            <span className="font-mono text-xs">{nextNonce(account)}</span>
           </div>
           <div className="flex items-center justify-between">
             <span className="text-sm font-medium">Number of Identities</span>
@@ -4,6 +4,7 @@ import {
   Transaction,
   Identity,
   AbstractAccountContractBuilder,
+  MessageEncoding,
 } from "chainsig-aa.js";
 import React, { useState } from "react";
 
@@ -24,6 +25,8 @@ import {
 import { Label } from "@/components/ui/label";
 import { Switch } from "@/components/ui/switch";
 import { useAccountData } from "@/hooks/useAccountData";
+import { useDomain } from "@/hooks/useDomain";
+import { nextNonce } from "@/lib/utils";
 import { useAccount } from "@/providers/AccountContext";
 
 export default function AccountPage() {
@@ -35,8 +38,9 @@ export default function AccountPage() {
   const { accountId, authIdentities } = useAccount();
   const [enableActAs, setEnableActAs] = useState(false);
   const { account, isLoading } = useAccountData();
+  const { domain } = useDomain();
 
-  if (isLoading || !account || !accountId) {
+  if (isLoading || !account || !accountId || !domain) {
     return (
       <div className="min-h-screen flex items-center justify-center bg-gray-50">
         <div className="text-center">Loading account...</div>
@@ -44,22 +48,24 @@ export default function AccountPage() {
     );
   }
 
-  const addIdentityWithAuthNonce =
+  // Transactions of the account page use the 0 nonce key lane
+  const nonce = nextNonce(account);
+
+  const addIdentityWithAuthNonce = (encoding: MessageEncoding) =>
     AbstractAccountContractBuilder.nonce.addIdentityWithAuth({
+      domain: { ...domain, encoding },
       account_id: accountId,
-      nonce: account?.nonce.toString(),
+      epoch: account.epoch.toString(),
+      nonce_key: "0",
+      nonce: nonce.toString(),
       permissions: {
         enable_act_as: enableActAs,
       },
     });
 
   const handleAddIdentity = async (config: AuthConfig) => {
-    if (!addIdentityWithAuthNonce) {
-      throw new Error("Canonicalized nonce is undefined");
-    }
-
     const { credentials, authIdentity } = await AuthAdapter.sign(
-      addIdentityWithAuthNonce,
+      addIdentityWithAuthNonce(AuthAdapter.encoding(config)),
       config
     );
 
@@ -68,7 +74,8 @@ export default function AccountPage() {
     if (!enableActAs) {
       transaction = AbstractAccountContractBuilder.transaction.addIdentity({
         accountId,
-        nonce: account.nonce ?? 0,
+        epoch: account.epoch,
+        nonce,
         identity_with_permissions: {
           identity: authIdentity,
           permissions: {
@@ -80,7 +87,8 @@ export default function AccountPage() {
       transaction =
         AbstractAccountContractBuilder.transaction.addIdentityWithAuth({
           accountId,
-          nonce: account.nonce ?? 0,
+          epoch: account.epoch,
+          nonce,
           auth: {
             identity_with_permissions: {
               identity: authIdentity,
@@ -104,7 +112,8 @@ export default function AccountPage() {
     const transaction =
       AbstractAccountContractBuilder.transaction.removeIdentity({
         accountId,
-        nonce: account.nonce ?? 0,
+        epoch: account.epoch,
+        nonce,
         identity,
       });
 
@@ -119,7 +128,8 @@ export default function AccountPage() {
     const transaction =
       AbstractAccountContractBuilder.transaction.removeAccount({
         accountId,
-        nonce: account.nonce ?? 0,
+        epoch: account.epoch,
+        nonce,
       });
 
     setAuthProps({
@@ -171,7 +181,7 @@ export default function AccountPage() {
               </div>
               <AuthenticationButtons
                 onAuth={handleAddIdentity}
-                nonce={addIdentityWithAuthNonce}
+                nonce={addIdentityWithAuthNonce("CanonicalJson")}
                 accountId={accountId}
                 mode="register"
               />
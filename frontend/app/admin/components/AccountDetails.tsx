@@ -8,6 +8,7 @@ import {
 import { Key } from "lucide-react";
 
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { nextNonce } from "@/lib/utils";
 
 interface AccountDetailsProps {
   account: Account & {
@@ -28,7 +29,7 @@ const AccountDetails: React.FC<AccountDetailsProps> = ({
             {account.account_id}
           </CardTitle>
           <p className="text-sm text-muted-foreground mt-1">
-            Nonce: {account.nonce}
+            Epoch: {account.epoch} · Nonce: {nextNonce(account)}
           </p>
         </div>
       </CardHeader>
@@ -1,8 +1,9 @@
 "use client";
 
 import {
-  Action,
   Identity,
+  SignResponse,
+  Transaction,
   AbstractAccountContractBuilder,
 } from "chainsig-aa.js";
 import React, { useEffect, useState } from "react";
@@ -32,6 +33,7 @@ import { useAbstractAccountContract } from "@/contracts/useAbstractAccountContra
 import { useToast } from "@/hooks/use-toast";
 import { useChains } from "@/hooks/useChains";
 import { useEnv } from "@/hooks/useEnv";
+import { nextNonce } from "@/lib/utils";
 import { useAccount } from "@/providers/AccountContext";
 
 type FormValues = {
@@ -46,11 +48,7 @@ type FormValues = {
 
 type AuthProps = {
   accountId: string;
-  transaction: {
-    account_id: string;
-    nonce: number;
-    action: Action;
-  };
+  transaction: Transaction;
   actAs?: Identity;
 };
 
@@ -210,7 +208,8 @@ export default function TransactionForm() {
       const userOpTransaction = AbstractAccountContractBuilder.transaction.sign(
         {
           accountId: accountId,
-          nonce: account.nonce,
+          epoch: account.epoch,
+          nonce: nextNonce(account),
           payloads: {
             contract_id: data.contractId,
             payloads: [
@@ -234,11 +233,7 @@ export default function TransactionForm() {
 
       setAuthProps({
         accountId: accountId,
-        transaction: {
-          account_id: accountId,
-          nonce: account.nonce,
-          action: userOpTransaction.action,
-        },
+        transaction: userOpTransaction,
         ...(actAs ? { actAs: actAsIdentity } : {}),
       });
 
@@ -295,9 +290,14 @@ export default function TransactionForm() {
           isOpen={authModalOpen}
           onClose={() => setAuthModalOpen(false)}
           {...authProps}
-          onSuccess={(result) =>
-            finalizeTransactionSigning(result as MPCSignature)
-          }
+          onSuccess={(result) => {
+            // One response per Sign payload, the transaction signs a single one
+            const [response] = result as SignResponse[];
+            if ("Failed" in response.status) {
+              throw new Error(response.status.Failed);
+            }
+            finalizeTransactionSigning(response.status.Signed as MPCSignature);
+          }}
         />
       )}
       <Card className="w-full md:max-w-2xl">
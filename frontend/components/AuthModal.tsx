@@ -16,6 +16,7 @@ import {
 import { Separator } from "@/components/ui/separator";
 import { useAbstractAccountContract } from "@/contracts/useAbstractAccountContract";
 import { useToast } from "@/hooks/use-toast";
+import { useDomain } from "@/hooks/useDomain";
 import { AuthConfig, AuthAdapter } from "@/lib/auth/AuthAdapter";
 import { NEAR_MAX_GAS } from "@/lib/constants";
 import { useAccount } from "@/providers/AccountContext";
@@ -39,20 +40,25 @@ export default function AuthModal({
   const { contract } = useAbstractAccountContract();
   const queryClient = useQueryClient();
   const { accountId } = useAccount();
+  const { domain } = useDomain();
 
-  const canonicalizedTransaction =
-    AbstractAccountContractBuilder.nonce.transaction(transaction);
+  const canonicalizedTransaction = domain
+    ? AbstractAccountContractBuilder.nonce.transaction(transaction, domain)
+    : undefined;
 
   const handleAuth = async (config: AuthConfig) => {
     try {
-      if (!contract || !canonicalizedTransaction) {
+      if (!contract || !domain || !canonicalizedTransaction) {
         throw new Error(
           "Contract not initialized or canonicalized transaction is undefined"
         );
       }
 
       const { credentials, authIdentity } = await AuthAdapter.sign(
-        canonicalizedTransaction,
+        AbstractAccountContractBuilder.nonce.transaction(transaction, {
+          ...domain,
+          encoding: AuthAdapter.encoding(config),
+        }),
         config
       );
 
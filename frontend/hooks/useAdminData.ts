@@ -14,16 +14,15 @@ export function useAdminData() {
     queryKey: ["accounts"],
     queryFn: async () => {
       if (!contract) throw new Error("Contract not initialized");
-      const accountIds = await contract.listAccountIds();
       const accounts: AccountWithId[] = [];
+      let cursor: number | null = 0;
 
-      for (const accountId of accountIds) {
-        const account = await contract.getAccountById({
-          account_id: accountId,
-        });
-        if (account) {
+      while (cursor !== null) {
+        const page = await contract.listAccounts({ cursor });
+        for (const [accountId, account] of page.items) {
           accounts.push({ ...account, account_id: accountId });
         }
+        cursor = page.next_cursor;
       }
 
       return accounts;
@@ -37,12 +36,10 @@ export function useAdminData() {
       if (!contract || !accountsQuery.data)
         throw new Error("Contract not initialized or no accounts");
 
+      // Accounts are read with their identities, so no call per account is needed
       const identitiesMap = new Map();
       for (const account of accountsQuery.data) {
-        const identities = await contract.listAuthIdentities({
-          account_id: account.account_id,
-        });
-        identitiesMap.set(account.account_id, identities);
+        identitiesMap.set(account.account_id, account.identities);
       }
       return identitiesMap;
     },
@@ -0,0 +1,24 @@
+import { useQuery } from "@tanstack/react-query";
+
+import { useAbstractAccountContract } from "@/contracts/useAbstractAccountContract";
+
+export function useDomain() {
+  const { contract } = useAbstractAccountContract();
+
+  const domainQuery = useQuery({
+    queryKey: ["domain"],
+    queryFn: async () => {
+      if (!contract) return null;
+      // Signers using another encoding replace it, see AuthAdapter.encoding
+      return contract.getDomain({ encoding: "CanonicalJson" });
+    },
+    enabled: !!contract,
+    // The domain only changes when the contract is redeployed
+    staleTime: Infinity,
+  });
+
+  return {
+    domain: domainQuery.data,
+    isLoading: domainQuery.isLoading,
+  };
+}
@@ -4,6 +4,7 @@ import {
   OIDCCredentials,
   AbstractAccountContractBuilder,
   Identity,
+  MessageEncoding,
 } from "chainsig-aa.js";
 
 import { Ethereum } from "@/lib/auth/Ethereum/Ethereum";
@@ -45,6 +46,11 @@ export class AuthAdapter {
     }
   }
 
+  // Default encoding of the identity type, which the signed message domain must include
+  static encoding(config: AuthConfig): MessageEncoding {
+    return config.type === "webauthn" ? "Sha256" : "CanonicalJson";
+  }
+
   static async getIdentity(config: AuthConfig): Promise<Identity> {
     switch (config.type) {
       case "wallet": {
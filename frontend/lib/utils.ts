@@ -1,3 +1,4 @@
+import { Account } from "chainsig-aa.js";
 import { clsx, type ClassValue } from "clsx";
 import { jwtDecode } from "jwt-decode";
 import { twMerge } from "tailwind-merge";
@@ -6,6 +7,11 @@ export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs));
 }
 
+// Next nonce of the nonce key lane, lanes that were never used start at 0
+export function nextNonce(account: Account, nonceKey = 0): number {
+  return account.nonces[nonceKey.toString()] ?? 0;
+}
+
 export function parseOIDCToken(token: string): {
   issuer: string;
   sub: string;
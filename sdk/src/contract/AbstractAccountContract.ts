@@ -31,16 +31,41 @@ export class AbstractAccountContract {
     this.contract = new Contract(nearAccount, contractId, {
       viewMethods: [
         'get_account_by_id',
+        'get_nonce',
         'list_account_ids',
+        'get_accounts_count',
+        'list_accounts',
+        'list_all_identities',
+        'is_account_id_reserved',
+        'is_creation_proof_required',
         'list_identities',
         'get_account_by_identity',
+        'get_account_count_by_identity',
         'get_all_contracts',
         'get_signer_account',
+        'get_signer',
+        'list_signers',
+        'get_domain',
+        'get_derivation_path',
+        'get_proposal',
+        'list_proposals',
+        'get_pending_recoveries',
+        'list_pending_operations',
+        'get_sponsor',
         'storage_balance_of',
       ],
       changeMethods: [
         'add_account',
+        'deposit',
         'auth',
+        'approve_recovery',
+        'finalize_recovery',
+        'execute_pending_operation',
+        'derive_address',
+        'sponsor_deposit',
+        'set_sponsor_policy',
+        'sponsor_withdraw',
+        'auth_sponsored',
         'storage_deposit',
         'storage_withdraw',
       ],
@@ -64,8 +89,30 @@ export class AbstractAccountContract {
     return this.contract.get_account_by_id(obj)
   }
 
-  async listAccountIds() {
-    return this.contract.list_account_ids()
+  async getNonce(obj: Parameters<AbstractAccountContractType['get_nonce']>[0]) {
+    return this.contract.get_nonce(obj)
+  }
+
+  async listAccountIds(
+    obj?: Parameters<AbstractAccountContractType['list_account_ids']>[0]
+  ) {
+    return this.contract.list_account_ids(obj)
+  }
+
+  async getAccountsCount() {
+    return this.contract.get_accounts_count()
+  }
+
+  async listAccounts(
+    obj?: Parameters<AbstractAccountContractType['list_accounts']>[0]
+  ) {
+    return this.contract.list_accounts(obj)
+  }
+
+  async listAllIdentities(
+    obj?: Parameters<AbstractAccountContractType['list_all_identities']>[0]
+  ) {
+    return this.contract.list_all_identities(obj)
   }
 
   async listAuthIdentities(
@@ -80,6 +127,14 @@ export class AbstractAccountContract {
     return this.contract.get_account_by_identity(obj)
   }
 
+  async getAccountCountByIdentity(
+    obj: Parameters<
+      AbstractAccountContractType['get_account_count_by_identity']
+    >[0]
+  ) {
+    return this.contract.get_account_count_by_identity(obj)
+  }
+
   async getAllContracts() {
     return this.contract.get_all_contracts()
   }
@@ -88,6 +143,142 @@ export class AbstractAccountContract {
     return this.contract.get_signer_account()
   }
 
+  async getSigner(
+    obj: Parameters<AbstractAccountContractType['get_signer']>[0]
+  ) {
+    return this.contract.get_signer(obj)
+  }
+
+  async listSigners() {
+    return this.contract.list_signers()
+  }
+
+  async isAccountIdReserved(
+    obj: Parameters<AbstractAccountContractType['is_account_id_reserved']>[0]
+  ) {
+    return this.contract.is_account_id_reserved(obj)
+  }
+
+  async isCreationProofRequired() {
+    return this.contract.is_creation_proof_required()
+  }
+
+  async getDomain(
+    obj: Parameters<AbstractAccountContractType['get_domain']>[0]
+  ) {
+    return this.contract.get_domain(obj)
+  }
+
+  async getDerivationPath(
+    obj: Parameters<AbstractAccountContractType['get_derivation_path']>[0]
+  ) {
+    return this.contract.get_derivation_path(obj)
+  }
+
+  async deriveAddress(
+    obj: Parameters<AbstractAccountContractType['derive_address']>[0]
+  ) {
+    return this.withSignerAccount(
+      this.contract.derive_address.bind(this.contract),
+      obj
+    )
+  }
+
+  async getProposal(
+    obj: Parameters<AbstractAccountContractType['get_proposal']>[0]
+  ) {
+    return this.contract.get_proposal(obj)
+  }
+
+  async listProposals(
+    obj: Parameters<AbstractAccountContractType['list_proposals']>[0]
+  ) {
+    return this.contract.list_proposals(obj)
+  }
+
+  async getPendingRecoveries(
+    obj: Parameters<AbstractAccountContractType['get_pending_recoveries']>[0]
+  ) {
+    return this.contract.get_pending_recoveries(obj)
+  }
+
+  async approveRecovery(
+    obj: Parameters<AbstractAccountContractType['approve_recovery']>[0]
+  ) {
+    return this.withSignerAccount(
+      this.contract.approve_recovery.bind(this.contract),
+      obj
+    )
+  }
+
+  async finalizeRecovery(
+    obj: Parameters<AbstractAccountContractType['finalize_recovery']>[0]
+  ) {
+    return this.withSignerAccount(
+      this.contract.finalize_recovery.bind(this.contract),
+      obj
+    )
+  }
+
+  async listPendingOperations(
+    obj: Parameters<AbstractAccountContractType['list_pending_operations']>[0]
+  ) {
+    return this.contract.list_pending_operations(obj)
+  }
+
+  async executePendingOperation(
+    obj: Parameters<
+      AbstractAccountContractType['execute_pending_operation']
+    >[0]
+  ) {
+    return this.withSignerAccount(
+      this.contract.execute_pending_operation.bind(this.contract),
+      obj
+    )
+  }
+
+  async getSponsor(
+    obj: Parameters<AbstractAccountContractType['get_sponsor']>[0]
+  ) {
+    return this.contract.get_sponsor(obj)
+  }
+
+  async sponsorDeposit(
+    obj: Parameters<AbstractAccountContractType['sponsor_deposit']>[0]
+  ) {
+    return this.withSignerAccount(
+      this.contract.sponsor_deposit.bind(this.contract),
+      obj
+    )
+  }
+
+  async setSponsorPolicy(
+    obj: Parameters<AbstractAccountContractType['set_sponsor_policy']>[0]
+  ) {
+    return this.withSignerAccount(
+      this.contract.set_sponsor_policy.bind(this.contract),
+      obj
+    )
+  }
+
+  async sponsorWithdraw(
+    obj: Parameters<AbstractAccountContractType['sponsor_withdraw']>[0]
+  ) {
+    return this.withSignerAccount(
+      this.contract.sponsor_withdraw.bind(this.contract),
+      obj
+    )
+  }
+
+  async authSponsored(
+    obj: Parameters<AbstractAccountContractType['auth_sponsored']>[0]
+  ) {
+    return this.withSignerAccount(
+      this.contract.auth_sponsored.bind(this.contract),
+      obj
+    )
+  }
+
   async addAccount(
     obj: Parameters<AbstractAccountContractType['add_account']>[0]
   ) {
@@ -97,6 +288,10 @@ export class AbstractAccountContract {
     )
   }
 
+  async deposit(obj: Parameters<AbstractAccountContractType['deposit']>[0]) {
+    return this.withSignerAccount(this.contract.deposit.bind(this.contract), obj)
+  }
+
   async auth(obj: Parameters<AbstractAccountContractType['auth']>[0]) {
     const { waitUntil, args, gas = NEAR_MAX_GAS, amount = 0 } = obj
 
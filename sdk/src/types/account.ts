@@ -1,14 +1,66 @@
 import type { OIDCIdentity, WalletIdentity, WebAuthnIdentity } from './auth'
+import type {
+  MultisigPolicy,
+  PendingOperation,
+  PendingRecovery,
+  RecoveryConfig,
+  TimelockPolicy,
+} from './user-operation'
 
 export interface Account {
   identities: IdentityWithPermissions[]
-  nonce: number // TODO: u128 on rust, check support on TS later
+  epoch: number
+  // Next nonce of each nonce key, TODO: u128 on rust, check support on TS later
+  nonces: Record<string, number>
+  multisig_policy: MultisigPolicy | null
+  recovery_config: RecoveryConfig | null
+  // One per identity approved by the guardians
+  pending_recoveries: PendingRecovery[]
+  recovery_nonce: number
+  timelock_policy: TimelockPolicy | null
+  pending_operations: PendingOperation[]
+  next_operation_id: number
+  // yoctoNEAR held by the account, pays for its signature fees and receives their refunds
+  balance: string
+  signing_path_mode: SigningPathMode
+  // NEP-141 balances held in custody, keyed by token contract
+  fungible_tokens: Record<string, string>
+  // NEP-171 token ids held in custody, keyed by token contract
+  non_fungible_tokens: Record<string, string[]>
+  // Native NEAR account running the proxy contract, set while it's being created
+  sub_account: string | null
 }
+
+// Account signs for the same keys with every identity, Identity derives them per act_as identity
+export type SigningPathMode = 'Identity' | 'Account'
+
 export interface IdentityWithPermissions {
   identity: Identity
   permissions: IdentityPermissions
 }
 
+export type AuthTypeNames =
+  | 'EthereumWallet'
+  | 'SolanaWallet'
+  | 'WebAuthn'
+  | 'OIDC'
+  | 'Account'
+
+// Fields left unset match any identity
+export interface IdentityFilter {
+  auth_type?: AuthTypeNames
+  // Full access identities when true, limited ones when false
+  full_access?: boolean
+  // Identities the account can act as, full access identities always can
+  enable_act_as?: boolean
+}
+
+// Pass next_cursor to the next call to continue the iteration, null once every entry was read
+export interface Page<T> {
+  items: T[]
+  next_cursor: number | null
+}
+
 export type IdentityPermissions = {
   enable_act_as: boolean
 } | null
@@ -1,6 +1,25 @@
 import { Contract } from 'near-api-js'
-import type { IdentityWithPermissions, Identity, Account } from './account'
-import type { UserOperation } from './user-operation'
+import type {
+  IdentityWithPermissions,
+  Identity,
+  IdentityFilter,
+  Account,
+  Page,
+} from './account'
+import type {
+  Auth,
+  CreationAuth,
+  Domain,
+  MessageEncoding,
+  PendingOperation,
+  PendingRecovery,
+  Proposal,
+  SignerConfig,
+  Sponsor,
+  SponsorPolicy,
+  RecoveryApproval,
+  UserOperation,
+} from './user-operation'
 import type { TxExecutionStatus } from '@near-js/types'
 import { Account as NearAccount } from 'near-api-js'
 
@@ -17,6 +36,14 @@ export type ExtendedContractChangeArgs<T = void> =
     waitUntil?: TxExecutionStatus
   }
 
+export type AddressChain = 'Evm' | 'Bitcoin' | 'Solana'
+
+export interface DerivedAddress {
+  path: string
+  public_key: string
+  address: string
+}
+
 export interface StorageBalance {
   total: string
   available: string
@@ -24,24 +51,135 @@ export interface StorageBalance {
 
 export type AbstractAccountContractType = Contract & {
   get_account_by_id: (args: { account_id: string }) => Promise<Account | null>
-  list_account_ids: () => Promise<string[]>
+  get_nonce: (args: {
+    account_id: string
+    nonce_key: number
+  }) => Promise<string | null>
+  list_account_ids: (args?: {
+    from_index?: number
+    limit?: number
+  }) => Promise<string[]>
+  get_accounts_count: () => Promise<number>
+  list_accounts: (args?: {
+    cursor?: number
+    limit?: number
+    filter?: IdentityFilter
+  }) => Promise<Page<[string, Account]>>
+  list_all_identities: (args?: {
+    cursor?: number
+    limit?: number
+    filter?: IdentityFilter
+  }) => Promise<Page<[string, IdentityWithPermissions]>>
+  is_account_id_reserved: (args: { account_id: string }) => Promise<boolean>
+  is_creation_proof_required: () => Promise<boolean>
   list_identities: (args: {
     account_id: string
+    filter?: IdentityFilter
   }) => Promise<IdentityWithPermissions[] | null>
-  get_account_by_identity: (args: { identity: Identity }) => Promise<string[]>
+  get_account_by_identity: (args: {
+    identity: Identity
+    from_index?: number
+    limit?: number
+  }) => Promise<string[]>
+  get_account_count_by_identity: (args: {
+    identity: Identity
+  }) => Promise<number>
   get_all_contracts: () => Promise<string[]>
   get_signer_account: () => Promise<string>
+  get_signer: (args: { signer_id: string }) => Promise<SignerConfig | null>
+  list_signers: () => Promise<[string, SignerConfig][]>
+  get_domain: (args: { encoding: MessageEncoding }) => Promise<Domain>
+  get_derivation_path: (args: {
+    account_id: string
+    // Only required in Identity signing path mode
+    identity?: Identity
+    path: string
+  }) => Promise<string>
+  // Change call, as it calls the signer contract. Use get_derivation_path to derive the key off-chain
+  derive_address: (
+    args: ExtendedContractChangeArgs<{
+      account_id: string
+      // Only required in Identity signing path mode
+      identity?: Identity
+      path: string
+      chain: AddressChain
+      // Defaults to the contract default signer
+      signer_id?: string
+    }>
+  ) => Promise<DerivedAddress>
+  get_proposal: (args: { proposal_id: string }) => Promise<Proposal | null>
+  list_proposals: (args: {
+    account_id: string
+  }) => Promise<[string, Proposal][]>
+  get_pending_recoveries: (args: {
+    account_id: string
+  }) => Promise<PendingRecovery[]>
+  list_pending_operations: (args: {
+    account_id: string
+  }) => Promise<PendingOperation[]>
+  approve_recovery: (
+    args: ExtendedContractChangeArgs<{
+      approval: RecoveryApproval
+      guardian: Auth
+    }>
+  ) => Promise<void>
+  finalize_recovery: (
+    args: ExtendedContractChangeArgs<{
+      account_id: string
+    }>
+  ) => Promise<void>
+  execute_pending_operation: (
+    args: ExtendedContractChangeArgs<{
+      account_id: string
+      operation_id: number
+    }>
+  ) => Promise<unknown>
+  deposit: (
+    args: ExtendedContractChangeArgs<{
+      account_id: string
+    }>
+  ) => Promise<string>
   add_account: (
     args: ExtendedContractChangeArgs<{
       account_id: string
       identity_with_permissions: IdentityWithPermissions
+      // Required when is_creation_proof_required returns true
+      auth?: CreationAuth
     }>
   ) => Promise<void>
+  // The attached deposit is credited to the account balance. Failed signature deposits and signer surpluses
+  // are refunded to the sponsor up to the fees it paid, then to the account balance, never to the caller
   auth: <T>(
     args: ExtendedContractChangeArgs<{
       user_op: UserOperation
     }>
   ) => Promise<T>
+  get_sponsor: (args: { sponsor_id: string }) => Promise<Sponsor | null>
+  sponsor_deposit: (
+    args: ExtendedContractChangeArgs<{
+      // Required to register as sponsor
+      policy?: SponsorPolicy
+    }>
+  ) => Promise<Sponsor>
+  set_sponsor_policy: (
+    args: ExtendedContractChangeArgs<{
+      policy: SponsorPolicy
+    }>
+  ) => Promise<void>
+  // Requires 1 yoctoNEAR attached
+  sponsor_withdraw: (
+    args: ExtendedContractChangeArgs<{
+      amount?: string
+    }>
+  ) => Promise<void>
+  auth_sponsored: <T>(
+    args: ExtendedContractChangeArgs<{
+      user_op: UserOperation
+      sponsor_id: string
+      // Signature fees the sponsor pays at most, never credited to the account. Capped by the policy max_fee_per_op
+      max_fee?: string
+    }>
+  ) => Promise<T>
   storage_balance_of: (args: {
     account_id: string
   }) => Promise<StorageBalance | null>
@@ -2,6 +2,7 @@ import type {
   Identity,
   IdentityWithPermissions,
   IdentityPermissions,
+  SigningPathMode,
 } from './account'
 import type {
   WebAuthnCredentials,
@@ -10,53 +11,267 @@ import type {
 } from './auth'
 
 export interface UserOperation {
-  auth: Auth
+  // A list of auths lets co-signers approve the same transaction in a single operation
+  auth: Auth | Auth[]
   act_as?: Identity
   transaction: Transaction
 }
 
 export interface Transaction {
+  // Lowercase 2 to 64 characters of a-z, 0-9, '-' and '_'. The contract signs the normalized id
   account_id: string
+  // Must match the account epoch, so signatures can't be replayed on a recreated account
+  epoch: number
+  // Lets independent sessions submit transactions concurrently, each lane has its own sequence
+  nonce_key: number
   nonce: number
-  action: Action
+  // Omitted from the signed message when unset
+  valid_until?: ValidUntil
+  // Executed in order and atomically
+  actions: Action[]
 }
 
+// Included in every signed message, binds signatures to a single deployment
+export interface Domain {
+  contract_id: string
+  network_id: string
+  version: number
+  // The encoding the message is signed in
+  encoding: MessageEncoding
+}
+
+export type ValidUntil =
+  // Block timestamp in nanoseconds
+  { Timestamp: string } | { BlockHeight: string }
+
 export type Action =
   | 'RemoveAccount'
   | { AddIdentityWithAuth: AddIdentityWithAuth }
   | { AddIdentity: IdentityWithPermissions }
   | { RemoveIdentity: Identity }
   | { Sign: SignPayloadsRequest }
+  | { SetMultisigPolicy: MultisigPolicy | null }
+  | { CancelProposal: string }
+  | { SetRecoveryConfig: RecoveryConfig | null }
+  // Never gated by the multisig and timelock policies
+  | 'CancelRecovery'
+  | { ApproveRecovery: RecoveryApproval }
+  | { SetTimelockPolicy: TimelockPolicy | null }
+  // Acts as a full access signer that didn't request the operation, never gated by the policies
+  | { CancelPendingOperation: number }
+  | { Withdraw: { receiver_id: string; amount: string } }
+  | { SetSigningPathMode: SigningPathMode }
+  | {
+      FtTransfer: {
+        token_id: string
+        receiver_id: string
+        amount: string
+        memo?: string
+      }
+    }
+  | {
+      NftTransfer: {
+        token_id: string
+        nft_id: string
+        receiver_id: string
+        memo?: string
+      }
+    }
+  // Gives up the tokens of the token contract held in custody, so they no longer block RemoveAccount
+  | { DropToken: { token_id: string } }
+  // Creates the <account_id>.<contract> sub-account, the initial balance must cover the proxy contract storage
+  | { CreateSubAccount: { initial_balance: string } }
+  | {
+      SubAccountFunctionCall: {
+        receiver_id: string
+        method_name: string
+        args: Record<string, unknown>
+        deposit: string
+        gas: string
+      }
+    }
+  | { SubAccountTransfer: { receiver_id: string; amount: string } }
+  // Credits the sub-account balance to the account
+  | 'DeleteSubAccount'
+
+export type ActionKind =
+  | 'RemoveAccount'
+  | 'AddIdentityWithAuth'
+  | 'AddIdentity'
+  | 'RemoveIdentity'
+  | 'Sign'
+  | 'SetMultisigPolicy'
+  | 'CancelProposal'
+  | 'SetRecoveryConfig'
+  | 'CancelRecovery'
+  | 'ApproveRecovery'
+  | 'SetTimelockPolicy'
+  | 'CancelPendingOperation'
+  | 'Withdraw'
+  | 'SetSigningPathMode'
+  | 'FtTransfer'
+  | 'NftTransfer'
+  | 'DropToken'
+  | 'CreateSubAccount'
+  | 'SubAccountFunctionCall'
+  | 'SubAccountTransfer'
+  | 'DeleteSubAccount'
+
+export interface MultisigPolicy {
+  threshold: number
+  // null means every action requires the threshold. Changing the policy, adding identities
+  // and setting the recovery config always require it, CancelPendingOperation and CancelRecovery never do
+  actions: ActionKind[] | null
+}
+
+export interface RecoveryConfig {
+  guardians: Identity[]
+  threshold: number
+  // Nanoseconds
+  delay: string
+  // Epochs of the account guardians, set by the contract
+  guardian_epochs?: Record<string, number>
+}
+
+export interface PendingRecovery {
+  identity: Identity
+  approvals: Identity[]
+  executable_at: string | null
+}
+
+// Signed by guardians in canonical JSON format, together with the contract domain
+export interface RecoveryApproval {
+  account_id: string
+  epoch: number
+  identity: Identity
+  nonce: number
+}
+
+export interface TimelockPolicy {
+  // Nanoseconds
+  delay: string
+  // SetTimelockPolicy is always delayed, CancelPendingOperation and CancelRecovery can't be listed
+  actions: ActionKind[]
+}
+
+export interface PendingOperation {
+  id: number
+  act_as: Identity
+  transaction: Transaction
+  requested_by: Identity[]
+  executable_at: string
+}
+
+export interface SponsorPolicy {
+  // Only these accounts are sponsored
+  accounts: string[]
+  // null sponsors every action
+  actions: ActionKind[] | null
+  // Maximum yoctoNEAR spent per day
+  daily_cap: string
+  // Gas reimbursed per action of a user operation, 10 Tgas when unset, whatever the relayer prepaid
+  gas_per_action?: string | null
+  // Signature fees paid at most per user operation, none when unset
+  max_fee_per_op?: string
+}
+
+export interface Sponsor {
+  balance: string
+  policy: SponsorPolicy
+  // Days since the unix epoch of the last sponsored user operation
+  day: number
+  spent_today: string
+}
+
+export interface Proposal {
+  act_as: Identity | null
+  transaction: Transaction
+  approvals: Identity[]
+  // Block timestamp in nanoseconds after which the proposal can no longer be approved
+  expires_at: number
+}
 
 export interface AddIdentityWithAuth {
   identity_with_permissions: IdentityWithPermissions
   credentials: Credentials
+  // Defaults to the encoding of the identity type, omitted from the signed message when unset
+  encoding?: MessageEncoding
+}
+
+// Signs { domain, account_id, action: 'AddAccount', identity, permissions }, proving the creator owns the identity
+export interface CreationAuth {
+  credentials: Credentials
+  // Defaults to the encoding of the identity type
+  encoding?: MessageEncoding
 }
 
 export interface Auth {
   identity: Identity
   credentials: Credentials
+  // Defaults to Sha256 for WebAuthn and CanonicalJson for the other identity types
+  encoding?: MessageEncoding
 }
 
+// Wallets and OIDC sign the JSON itself or the hex encoded bytes, WebAuthn challenges carry the base64url encoded bytes
+// Borsh messages start with their kind: 0 Transaction, 1 RecoveryApproval, 2 Action, 3 AccountCreation
+export type MessageEncoding = 'CanonicalJson' | 'Borsh' | 'Sha256'
+
 export type Credentials =
   | WebAuthnCredentials
   | WalletCredentials
   | OIDCCredentials
 
+export type SignatureScheme = 'Ecdsa' | 'Eddsa' | 'Schnorr'
+
 export interface SignPayloadsRequest {
-  contract_id: string
+  // Registered signer, defaults to the contract default signer
+  contract_id?: string
+  // Defaults to Ecdsa
+  scheme?: SignatureScheme
   payloads: SignRequest[]
 }
 
+// ECDSA and Schnorr payloads are 32 byte hashes, EdDSA payloads the message itself
 export interface SignRequest {
   payload: number[]
   path: string
   key_version: number
 }
 
+export interface SignerConfig {
+  // V1 signers only sign ECDSA payloads
+  version: 'V1' | 'V2'
+  schemes: SignatureScheme[]
+}
+
+export interface EcdsaSignature {
+  big_r: { affine_point: string }
+  s: { scalar: string }
+  recovery_id: number
+}
+
+export type SignResult =
+  | ({ scheme: 'Secp256k1' } & EcdsaSignature)
+  | { scheme: 'Ed25519'; signature: number[] }
+  | { scheme: 'Bip340'; signature: number[] }
+
+// Returned by auth for transactions with Sign actions, one per payload. The deposit of a Failed request
+// is refunded to the sponsor up to the fees it paid, then to the account balance rather than the caller
+export interface SignResponse {
+  signer_id: string
+  scheme: SignatureScheme
+  payload: number[]
+  path: string
+  key_version: number
+  status: { Signed: SignResult } | { Failed: string }
+}
+
 // TODO: Check where to place this
 export interface ActionSignableMessage {
+  domain: Domain
   account_id: string
+  epoch: string
+  nonce_key: string
   nonce: string
   action: string
   permissions?: IdentityPermissions
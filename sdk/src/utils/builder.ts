@@ -5,9 +5,12 @@ import type {
   OIDCIdentity,
 } from '../types/auth'
 import type {
+  Action,
   Credentials,
+  Domain,
   SignPayloadsRequest,
   Transaction,
+  ValidUntil,
 } from '../types/user-operation'
 import { isAddress, keccak256, type Hex } from 'viem'
 import { canonicalizeOrThrow } from './canonicalize'
@@ -36,62 +39,109 @@ export class AbstractAccountContractBuilder {
      */
     addIdentity: (args: {
       accountId: string
+      epoch: number
+      // Defaults to the 0 lane
+      nonceKey?: number
       nonce: number
+      validUntil?: ValidUntil
       identity_with_permissions: IdentityWithPermissions
     }): Transaction => ({
       account_id: args.accountId,
+      epoch: args.epoch,
+      nonce_key: args.nonceKey ?? 0,
       nonce: args.nonce,
-      action: {
-        AddIdentity: args.identity_with_permissions,
-      },
+      valid_until: args.validUntil,
+      actions: [{ AddIdentity: args.identity_with_permissions }],
     }),
 
     addIdentityWithAuth: (args: {
       accountId: string
+      epoch: number
+      // Defaults to the 0 lane
+      nonceKey?: number
       nonce: number
+      validUntil?: ValidUntil
       auth: {
         identity_with_permissions: IdentityWithPermissions
         credentials: Credentials
       }
     }): Transaction => ({
       account_id: args.accountId,
+      epoch: args.epoch,
+      nonce_key: args.nonceKey ?? 0,
       nonce: args.nonce,
-      action: {
-        AddIdentityWithAuth: args.auth,
-      },
+      valid_until: args.validUntil,
+      actions: [{ AddIdentityWithAuth: args.auth }],
     }),
 
     removeIdentity: (args: {
       accountId: string
+      epoch: number
+      // Defaults to the 0 lane
+      nonceKey?: number
       nonce: number
+      validUntil?: ValidUntil
       identity: Identity
     }): Transaction => ({
       account_id: args.accountId,
+      epoch: args.epoch,
+      nonce_key: args.nonceKey ?? 0,
       nonce: args.nonce,
-      action: {
-        RemoveIdentity: args.identity,
-      },
+      valid_until: args.validUntil,
+      actions: [{ RemoveIdentity: args.identity }],
     }),
 
     removeAccount: (args: {
       accountId: string
+      epoch: number
+      // Defaults to the 0 lane
+      nonceKey?: number
       nonce: number
+      validUntil?: ValidUntil
     }): Transaction => ({
       account_id: args.accountId,
+      epoch: args.epoch,
+      nonce_key: args.nonceKey ?? 0,
       nonce: args.nonce,
-      action: 'RemoveAccount',
+      valid_until: args.validUntil,
+      actions: ['RemoveAccount'],
     }),
 
     sign: (args: {
       accountId: string
+      epoch: number
+      // Defaults to the 0 lane
+      nonceKey?: number
       nonce: number
+      validUntil?: ValidUntil
       payloads: SignPayloadsRequest
     }): Transaction => ({
       account_id: args.accountId,
+      epoch: args.epoch,
+      nonce_key: args.nonceKey ?? 0,
       nonce: args.nonce,
-      action: {
-        Sign: args.payloads,
-      },
+      valid_until: args.validUntil,
+      actions: [{ Sign: args.payloads }],
+    }),
+
+    /**
+     * Executes the actions in order, if any of them fails none is applied
+     */
+    batch: (args: {
+      accountId: string
+      epoch: number
+      // Defaults to the 0 lane
+      nonceKey?: number
+      nonce: number
+      validUntil?: ValidUntil
+      actions: Action[]
+    }): Transaction => ({
+      account_id: args.accountId,
+      epoch: args.epoch,
+      nonce_key: args.nonceKey ?? 0,
+      nonce: args.nonce,
+      valid_until: args.validUntil,
+      actions: args.actions,
     }),
   }
 
@@ -127,12 +177,15 @@ export class AbstractAccountContractBuilder {
   }
 
   static nonce = {
-    transaction: (args: Transaction): string => {
-      return canonicalizeOrThrow(args)
+    transaction: (args: Transaction, domain: Domain): string => {
+      return canonicalizeOrThrow({ ...args, domain })
     },
 
     addIdentityWithAuth: (args: {
+      domain: Domain
       account_id: string
+      epoch: string
+      nonce_key: string
       nonce: string
       permissions: {
         enable_act_as: boolean